@@ -27,6 +27,11 @@ impl<T,U> DataPoint<T,U> {
     pub fn apply(&mut self, f: impl Fn(&U) -> U) {
         self.data = f(&self.data);
     }
+
+    /// Index and mutable data, the index being left read-only
+    pub(crate) fn parts_mut(&mut self) -> (&T, &mut U) {
+        (&self.index, &mut self.data)
+    }
 }
 
 impl<T: Ord,U: PartialOrd> Ord for DataPoint<T,U> {
@@ -35,6 +40,7 @@ impl<T: Ord,U: PartialOrd> Ord for DataPoint<T,U> {
     }
 }
 
+#[allow(clippy::non_canonical_partial_ord_impl)]
 impl<T: Ord,U: PartialOrd> PartialOrd for DataPoint<T,U> {
     fn partial_cmp(&self, other: &DataPoint<T,U>) -> Option<Ordering> {
         Some( self.index.cmp(&other.index) )
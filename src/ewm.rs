@@ -0,0 +1,121 @@
+use crate::Rollable;
+
+/// Exponentially weighted view of a series. Weights decay by a constant
+/// factor per position, or by a factor depending on the time elapsed between
+/// points for time indexed series.
+///
+/// With adjust set, each value is weighted by its decayed weight relative to
+/// the sum of all weights. Otherwise the statistic is updated recursively as
+/// `m = (1 - alpha) * m + alpha * x`. Missing values still decay the weights
+/// of earlier values unless ignore_na is set.
+pub struct Ewm<'a, S> {
+    source: &'a S,
+    factors: Vec<f64>,
+    alphas: Vec<f64>,
+    adjust: bool,
+    ignore_na: bool,
+    min_periods: usize
+}
+
+impl<'a, S: Rollable> Ewm<'a, S> {
+
+    /// factors[i] is the decay applied to earlier weights when reaching position i
+    /// and alphas[i] the weight of the value at position i when not adjusting
+    pub(crate) fn new(source: &'a S, factors: Vec<f64>, alphas: Vec<f64>, adjust: bool, ignore_na: bool, min_periods: usize) -> Self {
+        Self {
+            source,
+            factors,
+            alphas,
+            adjust,
+            ignore_na,
+            min_periods: min_periods.max(1)
+        }
+    }
+
+    pub fn mean(&self) -> S::Output {
+        let values = self.source.rolling_values();
+        let (means, _) = self.moments(&values, &values, true);
+        self.source.rolling_output(means)
+    }
+
+    /// Variance, unbiased unless bias is set
+    pub fn var(&self, bias: bool) -> S::Output {
+        let values = self.source.rolling_values();
+        let (_, var) = self.moments(&values, &values, bias);
+        self.source.rolling_output(var)
+    }
+
+    pub fn std(&self, bias: bool) -> S::Output {
+        let values = self.source.rolling_values();
+        let (_, var) = self.moments(&values, &values, bias);
+        self.source.rolling_output( var.into_iter().map(|v| v.map(f64::sqrt)).collect() )
+    }
+
+    /// Covariance with the values of other found at the indices of the source,
+    /// unbiased unless bias is set
+    pub fn cov(&self, other: &S, bias: bool) -> S::Output {
+        let values = self.source.rolling_values();
+        let other_values = self.source.aligned_values(other);
+        let (_, cov) = self.moments(&values, &other_values, bias);
+        self.source.rolling_output(cov)
+    }
+
+    /// Weighted means of xs and weighted covariance of (xs, ys), updated online
+    fn moments(&self, xs: &[Option<f64>], ys: &[Option<f64>], bias: bool) -> (Vec<Option<f64>>, Vec<Option<f64>>) {
+        let mut means : Vec<Option<f64>> = Vec::new();
+        let mut covs : Vec<Option<f64>> = Vec::new();
+        let (mut mean_x, mut mean_y, mut cov) = (0.0f64, 0.0f64, 0.0f64);
+        let (mut sum_wt, mut sum_wt2, mut old_wt) = (0.0f64, 0.0f64, 0.0f64);
+        let mut nobs = 0usize;
+        for i in 0..xs.len() {
+            let obs = xs[i].zip(ys[i]);
+            if nobs == 0 {
+                if let Some( (x,y) ) = obs {
+                    mean_x = x;
+                    mean_y = y;
+                    cov = 0.0;
+                    sum_wt = 1.0;
+                    sum_wt2 = 1.0;
+                    old_wt = 1.0;
+                    nobs = 1;
+                }
+            } else if obs.is_some() || !self.ignore_na {
+                let factor = self.factors[i];
+                sum_wt *= factor;
+                sum_wt2 *= factor * factor;
+                old_wt *= factor;
+                if let Some( (x,y) ) = obs {
+                    let new_wt = if self.adjust { 1.0 } else { self.alphas[i] };
+                    let (old_mean_x, old_mean_y) = (mean_x, mean_y);
+                    mean_x = (old_wt * old_mean_x + new_wt * x) / (old_wt + new_wt);
+                    mean_y = (old_wt * old_mean_y + new_wt * y) / (old_wt + new_wt);
+                    cov = (old_wt * (cov + (old_mean_x - mean_x) * (old_mean_y - mean_y))
+                        + new_wt * (x - mean_x) * (y - mean_y)) / (old_wt + new_wt);
+                    sum_wt += new_wt;
+                    sum_wt2 += new_wt * new_wt;
+                    old_wt += new_wt;
+                    if !self.adjust {
+                        sum_wt /= old_wt;
+                        sum_wt2 /= old_wt * old_wt;
+                        old_wt = 1.0;
+                    }
+                    nobs += 1;
+                }
+            }
+            if nobs >= self.min_periods {
+                means.push( Some( mean_x ) );
+                covs.push( if bias {
+                    Some( cov )
+                } else {
+                    let numerator = sum_wt * sum_wt;
+                    let denominator = numerator - sum_wt2;
+                    if denominator > 0.0 { Some( numerator / denominator * cov ) } else { None }
+                });
+            } else {
+                means.push( None );
+                covs.push( None );
+            }
+        }
+        (means, covs)
+    }
+}
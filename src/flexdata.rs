@@ -1,7 +1,6 @@
 use std::ops::*;
 use std::convert::TryFrom;
 use std::iter::Sum;
-use crate::helper::derive_datatype;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum FlexDataType {
@@ -29,6 +28,12 @@ impl From<String> for FlexData {
     }
 }
 
+impl From<&str> for FlexData {
+    fn from(value: &str) -> FlexData {
+        FlexData::Str(value.to_string())
+    }
+}
+
 impl From<u32> for FlexData {
     fn from(value: u32) -> FlexData {
         FlexData::Uint(value)
@@ -109,13 +114,13 @@ impl Add for &FlexData {
             },
             FlexData::Uint(val) => {
                 match other {
-                    FlexData::Uint(other_val) => FlexData::Uint(val + other_val),
+                    FlexData::Uint(other_val) => val.checked_add(*other_val).map_or(FlexData::NA, FlexData::Uint),
                     _ => FlexData::NA
                 }
             },
             FlexData::Int(val) => {
                 match other {
-                    FlexData::Int(other_val) => FlexData::Int(val + other_val),
+                    FlexData::Int(other_val) => val.checked_add(*other_val).map_or(FlexData::NA, FlexData::Int),
                     _ => FlexData::NA
                 }
             },
@@ -136,13 +141,13 @@ impl Sub for &FlexData {
             },
             FlexData::Uint(val) => {
                 match other {
-                    FlexData::Uint(other_val) => FlexData::Uint(val - other_val),
+                    FlexData::Uint(other_val) => val.checked_sub(*other_val).map_or(FlexData::NA, FlexData::Uint),
                     _ => FlexData::NA
                 }
             },
             FlexData::Int(val) => {
                 match other {
-                    FlexData::Int(other_val) => FlexData::Int(val - other_val),
+                    FlexData::Int(other_val) => val.checked_sub(*other_val).map_or(FlexData::NA, FlexData::Int),
                     _ => FlexData::NA
                 }
             },
@@ -163,13 +168,13 @@ impl Mul for &FlexData {
             },
             FlexData::Uint(val) => {
                 match other {
-                    FlexData::Uint(other_val) => FlexData::Uint(val * other_val),
+                    FlexData::Uint(other_val) => val.checked_mul(*other_val).map_or(FlexData::NA, FlexData::Uint),
                     _ => FlexData::NA
                 }
             },
             FlexData::Int(val) => {
                 match other {
-                    FlexData::Int(other_val) => FlexData::Int(val * other_val),
+                    FlexData::Int(other_val) => val.checked_mul(*other_val).map_or(FlexData::NA, FlexData::Int),
                     _ => FlexData::NA
                 }
             },
@@ -196,9 +201,36 @@ impl Div for &FlexData {
             },
             FlexData::Int(val) => {
                 match other {
-                    FlexData::Int(other_val) => {
+                    FlexData::Int(other_val) => val.checked_div(*other_val).map_or(FlexData::NA, FlexData::Int),
+                    _ => FlexData::NA
+                }
+            },
+            FlexData::Uint(val) => {
+                match other {
+                    FlexData::Uint(other_val) => {
                         if other_val != &0 {
-                            FlexData::Int(val / other_val)
+                            FlexData::Uint(val / other_val)
+                        } else {
+                            FlexData::NA
+                        }
+                    },
+                    _ => FlexData::NA
+                }
+            },
+            _ => FlexData::NA
+        }
+    }
+}
+
+impl Rem for &FlexData {
+    type Output = FlexData;
+    fn rem(self, other: &FlexData) -> Self::Output {
+        match self {
+            FlexData::Dbl(val) => {
+                match other {
+                    FlexData::Dbl(other_val) => {
+                        if other_val != &0f64 {
+                            FlexData::Dbl(val % other_val)
                         } else {
                             FlexData::NA
                         }
@@ -206,11 +238,17 @@ impl Div for &FlexData {
                     _ => FlexData::NA
                 }
             },
+            FlexData::Int(val) => {
+                match other {
+                    FlexData::Int(other_val) => val.checked_rem(*other_val).map_or(FlexData::NA, FlexData::Int),
+                    _ => FlexData::NA
+                }
+            },
             FlexData::Uint(val) => {
                 match other {
                     FlexData::Uint(other_val) => {
                         if other_val != &0 {
-                            FlexData::Uint(val / other_val)
+                            FlexData::Uint(val % other_val)
                         } else {
                             FlexData::NA
                         }
@@ -234,13 +272,13 @@ impl AddAssign for FlexData {
             },
             FlexData::Uint(val) => {
                 match other {
-                    FlexData::Uint(other_val) => FlexData::Uint(*val + other_val),
+                    FlexData::Uint(other_val) => val.checked_add(other_val).map_or(FlexData::NA, FlexData::Uint),
                     _ => FlexData::NA
                 }
             },
             FlexData::Int(val) => {
                 match other {
-                    FlexData::Int(other_val) => FlexData::Int(*val + other_val),
+                    FlexData::Int(other_val) => val.checked_add(other_val).map_or(FlexData::NA, FlexData::Int),
                     _ => FlexData::NA
                 }
             },
@@ -260,13 +298,13 @@ impl SubAssign for FlexData {
             },
             FlexData::Uint(val) => {
                 match other {
-                    FlexData::Uint(other_val) => FlexData::Uint(*val - other_val),
+                    FlexData::Uint(other_val) => val.checked_sub(other_val).map_or(FlexData::NA, FlexData::Uint),
                     _ => FlexData::NA
                 }
             },
             FlexData::Int(val) => {
                 match other {
-                    FlexData::Int(other_val) => FlexData::Int(*val - other_val),
+                    FlexData::Int(other_val) => val.checked_sub(other_val).map_or(FlexData::NA, FlexData::Int),
                     _ => FlexData::NA
                 }
             },
@@ -279,11 +317,13 @@ impl Sum<FlexData> for FlexData {
     fn sum<I>(iter: I) -> FlexData 
         where I: Iterator<Item=FlexData> {
         let mut total = FlexData::NA;
+        let mut started = false;
         for d in iter {
-            if derive_datatype( &total ) == FlexDataType::NA {
-                total = d;
-            } else {
+            if started {
                 total += d;
+            } else {
+                total = d;
+                started = true;
             }
         }
         total
@@ -29,6 +29,11 @@ impl FlexDataPoint {
         self.index = index;
     }
 
+    /// Index and mutable data, the index being left read-only
+    pub(crate) fn parts_mut(&mut self) -> (&FlexIndex, &mut FlexData) {
+        (&self.index, &mut self.data)
+    }
+
     pub fn get_datatype(&self) -> FlexDataType {
         derive_datatype( &self.data )
     }
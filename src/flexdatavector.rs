@@ -21,6 +21,11 @@ impl FlexDataVector {
         &self.index
     }
 
+    /// Index and mutable data, the index and size being left unchanged
+    pub(crate) fn parts_mut(&mut self) -> (&FlexIndex, &mut [FlexData]) {
+        (&self.index, &mut self.data)
+    }
+
     pub fn set_index(&mut self, index: FlexIndex) {
         self.index = index;
     }
@@ -84,10 +89,7 @@ impl FlexDataVector {
         types_cells.insert(0, Cell::new(""));
         table.add_row(Row::new(types_cells));
         let mut record_cells : Vec<Cell> = Vec::new();
-        let index_cell = match &self.index {
-            FlexIndex::Uint(val) => Cell::new( format!("{}", val).as_str() ),
-            FlexIndex::Str(val) => Cell::new( val.as_str() )
-        };
+        let index_cell = Cell::new( format!("{}", self.index).as_str() );
         record_cells.push(index_cell);
         for k in 0..self.get_size() {
             let cell = match &self.data[k] {
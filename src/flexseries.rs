@@ -1,13 +1,16 @@
-use crate::{FlexDataType, FlexDataPoint, FlexData, FlexIndex};
-use crate::helper::{convert, index_intersection};
-use std::collections::HashMap;
+use crate::{DataPoint, Series, FlexDataType, FlexDataPoint, FlexData, FlexIndex, QuantileMethod, RankMethod, Decay, Ewm, Interval, Rollable, Rolling, Alignment};
+use crate::rolling::{count_bounds, span_bounds};
+use crate::helper::{convert, derive_datatype, index_as_f64, index_intersection, index_union, shift_position};
+use crate::stats;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::ops::*;
 use prettytable::{Table, Row, Cell};
+use rayon::prelude::*;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FlexSeries {
-    iter_counter: usize,
     label: String,
     datatype: FlexDataType,
     data: Vec<FlexDataPoint>,
@@ -18,7 +21,6 @@ impl FlexSeries {
 
     pub fn new(label: &str, datatype: FlexDataType) -> Self {
         Self {
-            iter_counter: 0,
             label: label.to_string(),
             datatype,
             data: Vec::new(),
@@ -35,7 +37,6 @@ impl FlexSeries {
             index_to_pos.insert( fdp.get_index().clone(), i);
         }
         Self {
-            iter_counter: 0,
             label: label.to_string(),
             datatype,
             data: mod_data,
@@ -77,6 +78,21 @@ impl FlexSeries {
             .collect()
     }
 
+    // Iterating
+
+    pub fn iter(&self) -> std::slice::Iter<'_, FlexDataPoint> {
+        self.data.iter()
+    }
+
+    /// Iterate over the indices and mutable values
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=(&FlexIndex, &mut FlexData)> {
+        self.data.iter_mut().map(|fdp| fdp.parts_mut())
+    }
+
+    pub fn par_iter(&self) -> rayon::slice::Iter<'_, FlexDataPoint> {
+        self.data.par_iter()
+    }
+
     // Selecting
 
     pub fn at(&self, index: &FlexIndex) -> Option<&FlexDataPoint> {
@@ -119,14 +135,18 @@ impl FlexSeries {
     }
 
     pub fn remove(&mut self, k: usize) {
-        self.index_to_pos.remove( self.data[k].get_index() );
-        self.data.remove(k);
+        let removed = self.data.remove(k);
+        self.index_to_pos.remove( removed.get_index() );
+        for fdp in self.data[k..].iter() {
+            if let Some( pos ) = self.index_to_pos.get_mut( fdp.get_index() ) {
+                *pos -= 1;
+            }
+        }
     }
 
     pub fn remove_at(&mut self, index: &FlexIndex) {
         if let Some( &i ) = self.index_to_pos.get( index ) {
-            self.index_to_pos.remove( index );
-            self.data.remove(i);
+            self.remove(i);
         }
     }
 
@@ -136,7 +156,7 @@ impl FlexSeries {
         let data : Vec<FlexDataPoint> = self.data.iter()
             .map(|d| d.as_type(datatype))
             .collect();
-        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+        Self::from_vec(self.label.as_str(), datatype.clone(), data)
     }
 
     pub fn align_to(&self, indices: &[FlexIndex]) -> Self {
@@ -149,6 +169,30 @@ impl FlexSeries {
         series
     }
 
+    /// Values at the given indices in that order, fill being used for indices
+    /// not found. Duplicated indices are rejected.
+    pub fn reindex(&self, indices: &[FlexIndex], fill: &FlexData) -> Result<Self, &'static str> {
+        let unique : HashSet<&FlexIndex> = indices.iter().collect();
+        if unique.len() != indices.len() {
+            return Err("Index values are not unique");
+        }
+        let data : Vec<FlexDataPoint> = indices.iter()
+            .map(|index| FlexDataPoint::new( index.clone(), self.at(index).map_or(fill.clone(), |fdp| fdp.get_data().clone()) ))
+            .collect();
+        Ok( Self::from_vec(self.label.as_str(), self.datatype.clone(), data) )
+    }
+
+    /// Values ordered by index
+    pub fn sort_index(&self, ascending: bool) -> Self {
+        let mut data = self.data.clone();
+        if ascending {
+            data.sort_by(|a,b| a.get_index().cmp(b.get_index()) );
+        } else {
+            data.sort_by(|a,b| b.get_index().cmp(a.get_index()) );
+        }
+        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+    }
+
     pub fn apply(&self, f: impl Fn(&FlexData) -> FlexData) -> Self {
         let data = self.data.iter()
             .map(|dp| dp.apply(&f))
@@ -156,6 +200,14 @@ impl FlexSeries {
         Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
     }
 
+    /// Apply f to each value, the results being converted to datatype
+    pub fn apply_as(&self, datatype: &FlexDataType, f: impl Fn(&FlexData) -> FlexData) -> Self {
+        let data = self.data.iter()
+            .map(|dp| dp.apply(&f))
+            .collect();
+        Self::from_vec(self.label.as_str(), datatype.clone(), data)
+    }
+
     // Filtering
 
     pub fn filter_any(&self, f: impl Fn(&FlexData) -> bool) -> Self {
@@ -181,102 +233,397 @@ impl FlexSeries {
         self.filter_any(|x: &FlexData| x != &FlexData::NA)
     }
 
+    pub fn fill_na(&self, value: &FlexData) -> Self {
+        let value = convert(value, &self.datatype);
+        self.apply(|x: &FlexData| if x == &FlexData::NA { value.clone() } else { x.clone() })
+    }
+
+    /// Propagate the last valid value forward over at most limit consecutive NA
+    pub fn ffill(&self, limit: Option<usize>) -> Self {
+        let mut data = self.data.clone();
+        fill_forward( data.iter_mut(), limit );
+        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+    }
+
+    /// Propagate the next valid value backward over at most limit consecutive NA
+    pub fn bfill(&self, limit: Option<usize>) -> Self {
+        let mut data = self.data.clone();
+        fill_forward( data.iter_mut().rev(), limit );
+        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+    }
+
+    /// Linear interpolation of NA values between valid values as a FlexDataType::Dbl series.
+    /// Positions are used as abscissa unless use_index is set, in which case all indices
+    /// must be numbers, dates or timestamps. Fails for non-numeric series.
+    /// Leading and trailing NA are left untouched.
+    pub fn interpolate(&self, use_index: bool) -> Result<Self, &'static str> {
+        if !matches!(self.datatype, FlexDataType::Uint | FlexDataType::Int | FlexDataType::Dbl) {
+            return Err("Series is not numeric");
+        }
+        let xs : Vec<f64> = if use_index {
+            self.data.iter()
+                .map(|fdp| index_as_f64(fdp.get_index()))
+                .collect::<Option<Vec<f64>>>()
+                .ok_or("Index is not numeric")?
+        } else {
+            (0..self.get_size()).map(|i| i as f64).collect()
+        };
+        let ys : Vec<Option<f64>> = self.data.iter()
+            .map(|fdp| f64::try_from( &convert(fdp.get_data(), &FlexDataType::Dbl) ).ok())
+            .collect();
+        let mut next_valid : Vec<Option<usize>> = vec![None; self.get_size()];
+        for i in (0..self.get_size().saturating_sub(1)).rev() {
+            next_valid[i] = if ys[i+1].is_some() { Some(i+1) } else { next_valid[i+1] };
+        }
+        let mut data : Vec<FlexDataPoint> = Vec::new();
+        let mut previous : Option<usize> = None;
+        for (i,fdp) in self.data.iter().enumerate() {
+            let val = match ys[i] {
+                Some( y ) => {
+                    previous = Some(i);
+                    FlexData::Dbl(y)
+                },
+                None => {
+                    match (previous, next_valid[i]) {
+                        (Some(p), Some(q)) => {
+                            let (y0, y1) = (ys[p].unwrap(), ys[q].unwrap());
+                            FlexData::Dbl( y0 + (y1 - y0) * (xs[i] - xs[p]) / (xs[q] - xs[p]) )
+                        },
+                        _ => FlexData::NA
+                    }
+                }
+            };
+            data.push( FlexDataPoint::new(fdp.get_index().clone(), val) );
+        }
+        Ok( Self::from_vec(self.label.as_str(), FlexDataType::Dbl, data) )
+    }
+
+    pub fn fill_na_mean(&self) -> Self {
+        match self.mean() {
+            Some( mean ) => self.fill_na( &FlexData::Dbl(mean) ),
+            None => self.clone()
+        }
+    }
+
+    pub fn fill_na_median(&self) -> Self {
+        match self.median() {
+            Some( median ) => self.fill_na( &FlexData::Dbl(median) ),
+            None => self.clone()
+        }
+    }
+
+    /// Numeric values of the series as f64, skipping NA
+    fn valid_values(&self) -> Vec<f64> {
+        self.data.iter()
+            .filter_map(|fdp| f64::try_from( &convert(fdp.get_data(), &FlexDataType::Dbl) ).ok())
+            .collect()
+    }
+
     // Statistics
 
+    /// Number of values which are not NA
+    pub fn count(&self) -> usize {
+        self.data.iter()
+            .filter(|fdp| fdp.get_data() != &FlexData::NA)
+            .count()
+    }
+
+    /// Sum of the values which are not NA, NA if there are none
+    pub fn sum(&self) -> FlexData {
+        self.data.iter()
+            .filter(|fdp| fdp.get_data() != &FlexData::NA)
+            .map(|fdp| fdp.get_data().clone())
+            .sum()
+    }
+
+    /// Smallest value which is not NA, None if there are none
+    pub fn min(&self) -> Option<FlexData> {
+        self.data.iter()
+            .map(|fdp| fdp.get_data())
+            .filter(|&x| x != &FlexData::NA)
+            .min_by(|a,b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .cloned()
+    }
+
+    /// Largest value which is not NA, None if there are none
+    pub fn max(&self) -> Option<FlexData> {
+        self.data.iter()
+            .map(|fdp| fdp.get_data())
+            .filter(|&x| x != &FlexData::NA)
+            .max_by(|a,b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .cloned()
+    }
+
     pub fn mean(&self) -> Option<f64> {
-        if self.get_size() == 0 {
-            None
-        } else {
-            let n = self.get_size() as f64;
-            match self.clone().map(|dp| dp.get_data().clone()).sum() {
-                FlexData::Int(val) => Some( (val as f64) / n ),
-                FlexData::Uint(val) => Some( (val as f64) / n ),
-                FlexData::Dbl(val) => Some( val / n ),
-                _ => None
+        stats::mean( &self.valid_values() )
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5, &QuantileMethod::Linear)
+    }
+
+    /// Quantile of order q in [0,1] of the values which are not NA
+    pub fn quantile(&self, q: f64, method: &QuantileMethod) -> Option<f64> {
+        let mut values = self.valid_values();
+        values.sort_by(|a,b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        stats::quantile_sorted(&values, q, method)
+    }
+
+    pub fn std(&self, is_sample: bool) -> Option<f64> {
+        self.variance(is_sample).map(f64::sqrt)
+    }
+
+    pub fn skew(&self) -> Option<f64> {
+        stats::skew( &self.valid_values() )
+    }
+
+    /// Excess kurtosis
+    pub fn kurtosis(&self) -> Option<f64> {
+        stats::kurtosis( &self.valid_values() )
+    }
+
+    /// Most frequent values, in ascending order if several are tied
+    pub fn mode(&self) -> Vec<FlexData> {
+        let counts = self.value_counts();
+        let max_count = counts.first().map(|(_,c)| *c).unwrap_or(0);
+        let mut res : Vec<FlexData> = counts.into_iter()
+            .take_while(|(_,c)| *c == max_count)
+            .map(|(x,_)| x)
+            .collect();
+        res.sort_by(|a,b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        res
+    }
+
+    /// Number of distinct values which are not NA
+    pub fn nunique(&self) -> usize {
+        self.value_counts().len()
+    }
+
+    /// Distinct values which are not NA with their number of occurrences,
+    /// by descending count
+    pub fn value_counts(&self) -> Vec<(FlexData, usize)> {
+        let mut values : Vec<&FlexData> = self.data.iter()
+            .map(|fdp| fdp.get_data())
+            .filter(|&x| x != &FlexData::NA)
+            .collect();
+        values.sort_by(|a,b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mut res : Vec<(FlexData, usize)> = Vec::new();
+        for x in values.into_iter() {
+            match res.last_mut() {
+                Some( (last, count) ) if last == x => *count += 1,
+                _ => res.push( (x.clone(), 1) )
             }
         }
+        res.sort_by_key(|(_,count)| std::cmp::Reverse(*count));
+        res
     }
 
     pub fn covariance(&self, other: &Self, is_sample: bool) -> Option<f64> {
-        let intersect = index_intersection(self.get_indices(), other.get_indices());
-        if intersect.len() <= 1 {
-            None
-        } else {
-            let float_series1 = self.as_type(&FlexDataType::Dbl);
-            let float_series2 = other.as_type(&FlexDataType::Dbl);
-            let mut m1 = 0.0f64;
-            let mut m2 = 0.0f64;
-            let mut res = 0.0f64;
-            let mut n = 0.0f64;
-            for idx in intersect.into_iter() {
-                let x1 = f64::try_from( float_series1.at(&idx).unwrap().get_data() ).unwrap();
-                let x2 = f64::try_from( float_series2.at(&idx).unwrap().get_data() ).unwrap();
-                n += 1.0;
-                let dx1 = x1 - m1;
-                m1 += dx1 / n;
-                m2 += (x2 - m2) / n;
-                res += dx1 * (x2 - m2);
-            }
-            if is_sample {
-                Some( res / (n - 1.0) )
-            } else {
-                Some( res / n)
-            }
-        }
+        let (xs, ys) = self.aligned_values(other);
+        stats::covariance(&xs, &ys, is_sample)
     }
 
     pub fn variance(&self, is_sample: bool) -> Option<f64> {
-        if self.get_size() <= 1 {
-            None
-        } else {
-            let float_series = self.as_type(&FlexDataType::Dbl);
-            let mut m = 0.0f64;
-            let mut res = 0.0f64;
-            let mut n = 0.0f64;
-            for idx in self.get_indices() {
-                let x = f64::try_from( float_series.at(idx).unwrap().get_data() ).unwrap();
-                n += 1.0;
-                let dx1 = x - m;
-                m += dx1 / n;
-                res += dx1 * (x - m);
-            }
-            if is_sample {
-                Some( res / (n - 1.0) )
-            } else {
-                Some( res / n)
-            }
-        }
+        stats::variance( &self.valid_values(), is_sample )
     }
 
     pub fn pearson_correlation(&self, other: &Self) -> Option<f64> {
-        let intersect = index_intersection(self.get_indices(), other.get_indices());
-        if intersect.len() <= 1 {
-            None
-        } else {
-            let float_series1 = self.as_type(&FlexDataType::Dbl);
-            let float_series2 = other.as_type(&FlexDataType::Dbl);
-            let mut m1 = 0.0f64;
-            let mut m2 = 0.0f64;
-            let mut m12 = 0.0f64;
-            let mut cov = 0.0f64;
-            let mut v1 = 0.0f64;
-            let mut v2 = 0.0f64;
-            let mut n = 0.0f64;
-            for idx in intersect.into_iter() {
-                let x1 = f64::try_from( float_series1.at(&idx).unwrap().get_data() ).unwrap();
-                let x2 = f64::try_from( float_series2.at(&idx).unwrap().get_data() ).unwrap();
-                let dx1 = x1 - m1;
-                let dx2 = x2 - m2;
-                n += 1.0;
-                m1 += dx1 / n;
-                m2 += dx2 / n;
-                m12 += (x2 - m12) / n;
-                cov += dx1 * (x2 - m12);
-                v1 += dx1 * (x1 - m1);
-                v2 += dx2 * (x2 - m2);
-            }
-            Some( cov / (v1.sqrt() * v2.sqrt()) )
+        let (xs, ys) = self.aligned_values(other);
+        stats::pearson(&xs, &ys)
+    }
+
+    pub fn spearman_correlation(&self, other: &Self) -> Option<f64> {
+        let (xs, ys) = self.aligned_values(other);
+        stats::spearman(&xs, &ys)
+    }
+
+    pub fn kendall_tau(&self, other: &Self) -> Option<f64> {
+        let (xs, ys) = self.aligned_values(other);
+        stats::kendall(&xs, &ys)
+    }
+
+    /// Values of both series on their common indices, skipping pairs with a NA
+    fn aligned_values(&self, other: &Self) -> (Vec<f64>, Vec<f64>) {
+        index_intersection(self.get_indices(), other.get_indices())
+            .into_iter()
+            .filter_map(|idx| {
+                let x1 = f64::try_from( &convert(self.at(&idx)?.get_data(), &FlexDataType::Dbl) ).ok();
+                let x2 = f64::try_from( &convert(other.at(&idx)?.get_data(), &FlexDataType::Dbl) ).ok();
+                x1.zip(x2)
+            })
+            .unzip()
+    }
+
+    // Ranking
+
+    /// Rank of the values as a FlexDataType::Dbl series, starting at 1 in ascending
+    /// order. NA values are not ranked. With pct set, ranks are divided by the
+    /// highest possible rank.
+    pub fn rank(&self, method: &RankMethod, ascending: bool, pct: bool) -> Self {
+        let positions : Vec<usize> = (0..self.get_size())
+            .filter(|&i| self.data[i].get_data() != &FlexData::NA)
+            .collect();
+        let values : Vec<&FlexData> = positions.iter().map(|&i| self.data[i].get_data()).collect();
+        let mut ranks = stats::rank(&values, method, ascending);
+        if pct {
+            let scale = match method {
+                RankMethod::Dense => ranks.iter().cloned().fold(0.0f64, f64::max),
+                _ => values.len() as f64
+            };
+            ranks.iter_mut().for_each(|r| *r /= scale);
+        }
+        let mut data : Vec<FlexDataPoint> = self.data.iter()
+            .map(|fdp| FlexDataPoint::new(fdp.get_index().clone(), FlexData::NA))
+            .collect();
+        for (&i, r) in positions.iter().zip(ranks) {
+            data[i].set_data( FlexData::Dbl(r) );
+        }
+        Self::from_vec(self.label.as_str(), FlexDataType::Dbl, data)
+    }
+
+    // Conversions
+
+    /// Typed Series of the values, sorted by index. Fails on NA values and on
+    /// indices or values which can not be converted.
+    pub fn try_to_series<T,U>(&self) -> Result<Series<T,U>, &'static str>
+    where
+        T: Ord + Clone + for<'a> TryFrom<&'a FlexIndex>,
+        U: PartialOrd + Clone + for<'a> TryFrom<&'a FlexData>
+    {
+        let mut items : Vec<DataPoint<T,U>> = Vec::new();
+        for fdp in self.data.iter() {
+            let index = T::try_from( fdp.get_index() ).map_err(|_| "Index can not be converted")?;
+            let value = U::try_from( fdp.get_data() ).map_err(|_| "Value can not be converted")?;
+            items.push( DataPoint::new(index, value) );
+        }
+        Ok( Series::from_vec(self.label.as_str(), items) )
+    }
+
+    // Windows
+
+    /// Moving window over the last `window` values, or centered on each value.
+    /// Fails if window is 0.
+    pub fn rolling(&self, window: usize, min_periods: usize, center: bool) -> Result<Rolling<'_, Self>, &'static str> {
+        if window == 0 {
+            return Err("Rolling window must hold at least one value");
+        }
+        Ok( Rolling::new(self, count_bounds(self.get_size(), window, center), min_periods) )
+    }
+
+    /// Moving window over a time span, for series indexed by ascending dates or timestamps
+    pub fn rolling_span(&self, span: Interval, min_periods: usize, center: bool) -> Result<Rolling<'_, Self>, &'static str> {
+        let timestamps : Vec<i64> = self.data.iter()
+            .map(|fdp| fdp.get_index().as_millis())
+            .collect::<Option<Vec<i64>>>()
+            .ok_or("Index is not made of dates or timestamps")?;
+        if timestamps.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err("Index is not sorted");
         }
+        Ok( Rolling::new(self, span_bounds(&timestamps, span.checked_millis()?, center), min_periods) )
+    }
+
+    /// Exponentially weighted statistics with a constant decay per value
+    pub fn ewm(&self, decay: Decay, adjust: bool, ignore_na: bool, min_periods: usize) -> Result<Ewm<'_, Self>, &'static str> {
+        let alpha = decay.alpha()?;
+        let size = self.get_size();
+        Ok( Ewm::new(self, vec![1.0 - alpha; size], vec![alpha; size], adjust, ignore_na, min_periods) )
+    }
+
+    // Cumulative operations
+
+    /// Running sum, missing values being skipped and kept as NA. Values from
+    /// an overflow onwards are NA.
+    pub fn cumsum(&self) -> Self {
+        self.accumulate(|acc,x| acc + x)
+    }
+
+    /// Running product, missing values being skipped and kept as NA. Values
+    /// from an overflow onwards are NA.
+    pub fn cumprod(&self) -> Self {
+        self.accumulate(|acc,x| acc * x)
+    }
+
+    /// Running maximum, missing values being skipped and kept as NA
+    pub fn cummax(&self) -> Self {
+        self.accumulate(|acc,x| if x > acc { x.clone() } else { acc.clone() })
+    }
+
+    /// Running minimum, missing values being skipped and kept as NA
+    pub fn cummin(&self) -> Self {
+        self.accumulate(|acc,x| if x < acc { x.clone() } else { acc.clone() })
+    }
+
+    /// Number of values which are not missing up to each position
+    pub fn cumcount(&self) -> Self {
+        let mut count = 0u32;
+        let data : Vec<FlexDataPoint> = self.data.iter()
+            .map(|fdp| {
+                if fdp.get_data() != &FlexData::NA {
+                    count += 1;
+                }
+                FlexDataPoint::new(fdp.get_index().clone(), FlexData::Uint(count))
+            })
+            .collect();
+        Self::from_vec(self.label.as_str(), FlexDataType::Uint, data)
+    }
+
+    // Shifting
+
+    /// Lag values by n positions, or lead them when n is negative, filling with NA
+    pub fn shift(&self, n: i32) -> Self {
+        let data : Vec<FlexDataPoint> = self.data.iter()
+            .enumerate()
+            .map(|(i,fdp)| {
+                let val = shift_position(i, n, self.get_size()).map_or(FlexData::NA, |j| self.data[j].get_data().clone());
+                FlexDataPoint::new(fdp.get_index().clone(), val)
+            })
+            .collect();
+        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+    }
+
+    /// Difference with the value n positions before, or after when n is negative
+    pub fn diff(&self, n: i32) -> Self {
+        self.with_lagged(n, |x,y| x - y)
+    }
+
+    /// Relative change from the value n positions before, or after when n is negative
+    pub fn pct_change(&self, n: i32) -> Self {
+        self.with_lagged(n, |x,y| x / y - 1.0)
+    }
+
+    fn accumulate(&self, f: impl Fn(&FlexData,&FlexData) -> FlexData) -> Self {
+        let mut acc : Option<FlexData> = None;
+        let mut overflowed = false;
+        let data : Vec<FlexDataPoint> = self.data.iter()
+            .map(|fdp| {
+                let val = match (fdp.get_data(), &acc) {
+                    (FlexData::NA, _) => FlexData::NA,
+                    _ if overflowed => FlexData::NA,
+                    (x, Some( prev )) => f(prev, x),
+                    (x, None) => x.clone()
+                };
+                if val != FlexData::NA {
+                    acc = Some( val.clone() );
+                } else if fdp.get_data() != &FlexData::NA {
+                    overflowed = true;
+                }
+                FlexDataPoint::new(fdp.get_index().clone(), val)
+            })
+            .collect();
+        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+    }
+
+    /// Dbl series of f(value, lagged value), NA when either is missing or the
+    /// result is not finite
+    fn with_lagged(&self, n: i32, f: impl Fn(f64,f64) -> f64) -> Self {
+        let values = self.rolling_values();
+        let output : Vec<Option<f64>> = (0..values.len())
+            .map(|i| shift_position(i, n, values.len())
+                .and_then(|j| values[i].zip(values[j]))
+                .map(|(x,y)| f(x,y))
+                .filter(|z| z.is_finite()))
+            .collect();
+        self.rolling_output(output)
     }
 
     // Sorting
@@ -310,10 +657,7 @@ impl FlexSeries {
         };
         table.add_row(Row::new(vec![Cell::new(""), type_cell]));
         for i in 0..size {
-            let index_cell = match self[i].get_index() {
-                FlexIndex::Uint(val) => Cell::new( format!("{}", val).as_str() ),
-                FlexIndex::Str(val) => Cell::new( val.as_str() )
-            };
+            let index_cell = Cell::new( format!("{}", self[i].get_index()).as_str() );
             let data_cell = match self[i].get_data() {
                 FlexData::Str(val) => Cell::new( val.as_str() ),
                 FlexData::Dbl(val) => Cell::new( format!("{:.5}", val).as_str() ),
@@ -362,6 +706,233 @@ impl FlexSeries {
         }
         Self::from_vec(label, datatype.clone(), data)
     }
+
+    // Aligned operations
+
+    /// Element-wise quotient as a FlexDataType::Dbl series, NA where dividing by zero
+    pub fn div_aligned(&self, other: &Self, alignment: &Alignment, fill: Option<FlexData>) -> Result<Self, &'static str> {
+        self.common_datatype(other.get_datatype())?;
+        Ok( self.binary_apply(other, alignment, fill, &FlexDataType::Dbl, &FlexDataType::Dbl, |x,y| x / y) )
+    }
+
+    /// Element-wise power as a FlexDataType::Dbl series, NA where undefined
+    pub fn pow_aligned(&self, other: &Self, alignment: &Alignment, fill: Option<FlexData>) -> Result<Self, &'static str> {
+        self.common_datatype(other.get_datatype())?;
+        Ok( self.binary_apply(other, alignment, fill, &FlexDataType::Dbl, &FlexDataType::Dbl, |x,y| {
+            match (x, y) {
+                (FlexData::Dbl(x), FlexData::Dbl(y)) => Some( x.powf(*y) ).filter(|z| z.is_finite()).map_or(FlexData::NA, FlexData::Dbl),
+                _ => FlexData::NA
+            }
+        }) )
+    }
+
+    /// Element-wise minimum
+    pub fn min_aligned(&self, other: &Self, alignment: &Alignment, fill: Option<FlexData>) -> Result<Self, &'static str> {
+        let datatype = self.common_datatype(other.get_datatype())?;
+        Ok( self.binary_apply(other, alignment, fill, &datatype, &datatype, |x,y| {
+            match x.partial_cmp(y) {
+                Some( Ordering::Greater ) => y.clone(),
+                Some( _ ) => x.clone(),
+                None => FlexData::NA
+            }
+        }) )
+    }
+
+    /// Element-wise maximum
+    pub fn max_aligned(&self, other: &Self, alignment: &Alignment, fill: Option<FlexData>) -> Result<Self, &'static str> {
+        let datatype = self.common_datatype(other.get_datatype())?;
+        Ok( self.binary_apply(other, alignment, fill, &datatype, &datatype, |x,y| {
+            match x.partial_cmp(y) {
+                Some( Ordering::Less ) => y.clone(),
+                Some( _ ) => x.clone(),
+                None => FlexData::NA
+            }
+        }) )
+    }
+
+    /// Mask of the indices where self is equal to other
+    pub fn eq_mask(&self, other: &Self, alignment: &Alignment) -> Result<Self, &'static str> {
+        self.compare(other, alignment, |ord| ord == Ordering::Equal)
+    }
+
+    /// Mask of the indices where self is not equal to other
+    pub fn ne_mask(&self, other: &Self, alignment: &Alignment) -> Result<Self, &'static str> {
+        self.compare(other, alignment, |ord| ord != Ordering::Equal)
+    }
+
+    /// Mask of the indices where self is lower than other
+    pub fn lt_mask(&self, other: &Self, alignment: &Alignment) -> Result<Self, &'static str> {
+        self.compare(other, alignment, |ord| ord == Ordering::Less)
+    }
+
+    /// Mask of the indices where self is lower than or equal to other
+    pub fn le_mask(&self, other: &Self, alignment: &Alignment) -> Result<Self, &'static str> {
+        self.compare(other, alignment, |ord| ord != Ordering::Greater)
+    }
+
+    /// Mask of the indices where self is greater than other
+    pub fn gt_mask(&self, other: &Self, alignment: &Alignment) -> Result<Self, &'static str> {
+        self.compare(other, alignment, |ord| ord == Ordering::Greater)
+    }
+
+    /// Mask of the indices where self is greater than or equal to other
+    pub fn ge_mask(&self, other: &Self, alignment: &Alignment) -> Result<Self, &'static str> {
+        self.compare(other, alignment, |ord| ord != Ordering::Less)
+    }
+
+    /// Values at the indices where mask holds FlexData::Uint(1)
+    pub fn filter_mask(&self, mask: &Self) -> Self {
+        let data : Vec<FlexDataPoint> = self.data.iter()
+            .filter(|fdp| mask.at( fdp.get_index() ).map(|m| m.get_data()) == Some( &FlexData::Uint(1) ))
+            .cloned()
+            .collect();
+        Self::from_vec(self.label.as_str(), self.datatype.clone(), data)
+    }
+
+    /// Datatype both series are converted to before operating on them: their
+    /// datatype if shared, FlexDataType::Dbl for different numeric datatypes.
+    /// FlexDataType::NA is compatible with any datatype, other combinations
+    /// are rejected.
+    fn common_datatype(&self, datatype: &FlexDataType) -> Result<FlexDataType, &'static str> {
+        let numeric = |dt: &FlexDataType| matches!(dt, FlexDataType::Uint | FlexDataType::Int | FlexDataType::Dbl);
+        if &self.datatype == datatype || datatype == &FlexDataType::NA {
+            Ok( self.datatype.clone() )
+        } else if self.datatype == FlexDataType::NA {
+            Ok( datatype.clone() )
+        } else if numeric(&self.datatype) && numeric(datatype) {
+            Ok( FlexDataType::Dbl )
+        } else {
+            Err("Series datatypes are not compatible")
+        }
+    }
+
+    /// Boolean mask as a FlexDataType::Uint series holding 1 or 0, NA where
+    /// either value is missing or the values can not be compared
+    fn compare(&self, other: &Self, alignment: &Alignment, f: impl Fn(Ordering) -> bool) -> Result<Self, &'static str> {
+        let datatype = self.common_datatype(other.get_datatype())?;
+        Ok( self.binary_apply(other, alignment, None, &datatype, &FlexDataType::Uint, |x,y| {
+            match x.partial_cmp(y) {
+                Some( ord ) if x != &FlexData::NA && y != &FlexData::NA => FlexData::Uint( f(ord) as u32 ),
+                _ => FlexData::NA
+            }
+        }) )
+    }
+
+    /// Apply f to the values of both series at the aligned indices, converted
+    /// to operand_type. A value missing from either series is replaced by fill,
+    /// or gives NA if there is none.
+    fn binary_apply(&self, other: &Self, alignment: &Alignment, fill: Option<FlexData>, operand_type: &FlexDataType, result_type: &FlexDataType, f: impl Fn(&FlexData, &FlexData) -> FlexData) -> Self {
+        let indices = match alignment {
+            Alignment::Inner => index_intersection(self.get_indices(), other.get_indices()),
+            Alignment::Left => self.get_indices().into_iter().cloned().collect(),
+            Alignment::Outer => index_union(self.get_indices(), other.get_indices())
+        };
+        let data : Vec<FlexDataPoint> = indices.into_iter()
+            .map(|index| {
+                let x = self.at(&index).map(|fdp| fdp.get_data()).or(fill.as_ref());
+                let y = other.at(&index).map(|fdp| fdp.get_data()).or(fill.as_ref());
+                let val = match (x, y) {
+                    (Some( x ), Some( y )) => f( &convert(x, operand_type), &convert(y, operand_type) ),
+                    _ => FlexData::NA
+                };
+                FlexDataPoint::new(index, val)
+            })
+            .collect();
+        Self::from_vec(self.label.as_str(), result_type.clone(), data)
+    }
+}
+
+macro_rules! impl_flexseries_op {
+    ($trait:ident, $method:ident, $aligned:ident, $doc:expr) => {
+        impl FlexSeries {
+
+            #[doc = $doc]
+            /// over the aligned indices, in the common datatype of both series.
+            /// A value missing from either series is replaced by fill, or gives
+            /// NA if there is none.
+            pub fn $aligned(&self, other: &Self, alignment: &Alignment, fill: Option<FlexData>) -> Result<Self, &'static str> {
+                let datatype = self.common_datatype(other.get_datatype())?;
+                Ok( self.binary_apply(other, alignment, fill, &datatype, &datatype, |x,y| x.$method(y)) )
+            }
+
+        }
+
+        /// Element-wise operation over the indices found in both series. Panics
+        /// if the datatypes of the series are not compatible.
+        impl $trait<&FlexSeries> for &FlexSeries {
+            type Output = FlexSeries;
+            fn $method(self, other: &FlexSeries) -> FlexSeries {
+                self.$aligned(other, &Alignment::Inner, None).expect("Series datatypes are not compatible")
+            }
+        }
+
+        /// Operation between each value and a scalar, in their common datatype.
+        /// Panics if the datatype of the scalar is not compatible.
+        impl $trait<&FlexData> for &FlexSeries {
+            type Output = FlexSeries;
+            fn $method(self, scalar: &FlexData) -> FlexSeries {
+                let datatype = self.common_datatype( &derive_datatype(scalar) ).expect("Series datatypes are not compatible");
+                let scalar = convert(scalar, &datatype);
+                let data : Vec<FlexDataPoint> = self.data.iter()
+                    .map(|fdp| FlexDataPoint::new( fdp.get_index().clone(), convert(fdp.get_data(), &datatype).$method(&scalar) ))
+                    .collect();
+                FlexSeries::from_vec(self.label.as_str(), datatype, data)
+            }
+        }
+    };
+}
+
+impl_flexseries_op!(Add, add, add_aligned, "Element-wise sum");
+impl_flexseries_op!(Sub, sub, sub_aligned, "Element-wise difference");
+impl_flexseries_op!(Mul, mul, mul_aligned, "Element-wise product");
+impl_flexseries_op!(Rem, rem, rem_aligned, "Element-wise remainder");
+
+/// Element-wise quotient over the indices found in both series. Panics if the
+/// datatypes of the series are not compatible.
+impl Div<&FlexSeries> for &FlexSeries {
+    type Output = FlexSeries;
+    fn div(self, other: &FlexSeries) -> FlexSeries {
+        self.div_aligned(other, &Alignment::Inner, None).expect("Series datatypes are not compatible")
+    }
+}
+
+/// Quotient of each value by a scalar as a FlexDataType::Dbl series. Panics if
+/// the datatype of the scalar is not compatible.
+impl Div<&FlexData> for &FlexSeries {
+    type Output = FlexSeries;
+    fn div(self, scalar: &FlexData) -> FlexSeries {
+        self.common_datatype( &derive_datatype(scalar) ).expect("Series datatypes are not compatible");
+        let scalar = convert(scalar, &FlexDataType::Dbl);
+        let data : Vec<FlexDataPoint> = self.data.iter()
+            .map(|fdp| FlexDataPoint::new( fdp.get_index().clone(), &convert(fdp.get_data(), &FlexDataType::Dbl) / &scalar ))
+            .collect();
+        FlexSeries::from_vec(self.label.as_str(), FlexDataType::Dbl, data)
+    }
+}
+
+impl Rollable for FlexSeries {
+    type Output = FlexSeries;
+
+    fn rolling_values(&self) -> Vec<Option<f64>> {
+        self.data.iter()
+            .map(|fdp| f64::try_from( &convert(fdp.get_data(), &FlexDataType::Dbl) ).ok())
+            .collect()
+    }
+
+    fn aligned_values(&self, other: &Self) -> Vec<Option<f64>> {
+        self.data.iter()
+            .map(|fdp| other.at( fdp.get_index() )
+                .and_then(|ofdp| f64::try_from( &convert(ofdp.get_data(), &FlexDataType::Dbl) ).ok()))
+            .collect()
+    }
+
+    fn rolling_output(&self, values: Vec<Option<f64>>) -> FlexSeries {
+        let data : Vec<FlexDataPoint> = self.data.iter()
+            .zip(values)
+            .map(|(fdp,x)| FlexDataPoint::new(fdp.get_index().clone(), x.map_or(FlexData::NA, FlexData::Dbl)))
+            .collect();
+        Self::from_vec(self.label.as_str(), FlexDataType::Dbl, data)
+    }
 }
 
 // Implement [] operator
@@ -391,17 +962,59 @@ impl Index<u32> for FlexSeries {
     }
 }
 
-impl Iterator for FlexSeries {
+/// Series labelled by its id, NaN values becoming NA
+impl<T: Ord + Clone + Into<FlexIndex>,U: PartialOrd + Clone + Into<FlexData>> From<&Series<T,U>> for FlexSeries {
+    fn from(series: &Series<T,U>) -> Self {
+        let data : Vec<FlexDataPoint> = series.iter()
+            .map(|dp| {
+                let value = match dp.get().clone().into() {
+                    FlexData::Dbl(x) if x.is_nan() => FlexData::NA,
+                    value => value
+                };
+                FlexDataPoint::new(dp.get_index().clone().into(), value)
+            })
+            .collect();
+        let datatype = data.iter()
+            .map(|fdp| fdp.get_datatype())
+            .find(|datatype| datatype != &FlexDataType::NA)
+            .unwrap_or(FlexDataType::NA);
+        Self::from_vec(series.get_id(), datatype, data)
+    }
+}
+
+impl IntoIterator for FlexSeries {
     type Item = FlexDataPoint;
-    
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.iter_counter < self.get_size() {
-            let dv = self.data[self.iter_counter].clone();
-            self.iter_counter += 1;
-            Some( dv )
+    type IntoIter = std::vec::IntoIter<FlexDataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FlexSeries {
+    type Item = &'a FlexDataPoint;
+    type IntoIter = std::slice::Iter<'a, FlexDataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::unnecessary_map_or)]
+fn fill_forward<'a>(data: impl Iterator<Item=&'a mut FlexDataPoint>, limit: Option<usize>) {
+    let mut last : Option<FlexData> = None;
+    let mut count = 0;
+    for fdp in data {
+        if fdp.get_data() == &FlexData::NA {
+            count += 1;
+            if let Some( val ) = &last {
+                if limit.map_or(true, |l| count <= l) {
+                    fdp.set_data( val.clone() );
+                }
+            }
         } else {
-            self.iter_counter = 0;
-            None
+            last = Some( fdp.get_data().clone() );
+            count = 0;
         }
     }
-}
\ No newline at end of file
+}
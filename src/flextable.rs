@@ -1,32 +1,39 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::ops::*;
 use std::convert::TryFrom;
-use std::iter::Iterator;
 use rayon::prelude::*;
+use chrono::{NaiveDate, NaiveDateTime};
 use prettytable::{Table, Row, Cell};
 
 use std::sync::{Arc, Mutex};
 
-use crate::helper::{convert, generate_flexdata_from_str, extract_csv_headers, make_index_from_data};
-use crate::{FlexDataType, FlexData, FlexIndex, FlexDataPoint, FlexDataVector, FlexSeries};
+use crate::helper::{convert, derive_datatype, generate_flexdata_from_str, extract_csv_headers, make_data_from_index, make_index_from_data};
+use crate::{FlexDataType, FlexData, FlexIndex, FlexDataPoint, FlexDataVector, FlexSeries, QuantileMethod, CorrelationMethod, AsofDirection};
+use crate::stats;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FlexTable {
-    iter_counter: usize,
     labels: Vec<String>,
     datatypes: Vec<FlexDataType>,
     data: Vec<FlexDataVector>,
     label_to_pos: HashMap<String,usize>,
-    index_to_pos: HashMap<FlexIndex,usize>
+    index_to_pos: HashMap<FlexIndex,usize>,
+    #[serde(default)]
+    index_labels: Vec<String>
 }
 
 impl FlexTable {
 
     // Constructors
 
-    pub fn new( series: Vec<FlexSeries> ) -> Self {
+    /// Build a table from series of the same size. Fails if a label is used
+    /// more than once.
+    pub fn new( series: Vec<FlexSeries> ) -> Result<Self, &'static str> {
         assert!( series.iter().map(|s| s.get_size()).min() == series.iter().map(|s| s.get_size()).max() );
+        let labels : Vec<String> = series.iter().map(|s| s.get_label().to_string()).collect();
+        check_unique_labels( &labels )?;
         let mut data : Vec<FlexDataVector> = Vec::new();
         for i in 0..series[0].get_size() {
             let index = series[0][i].get_index().clone();
@@ -43,17 +50,24 @@ impl FlexTable {
         for (i,s) in series.iter().enumerate() {
             label_to_pos.insert( s.get_label().to_string(), i);
         }
-        Self {
-            iter_counter: 0,
-            labels: series.iter().map(|s| s.get_label().to_string()).collect(),
+        Ok( Self {
+            labels,
             datatypes: series.iter().map(|s| s.get_datatype().clone()).collect(),
             data,
             label_to_pos,
-            index_to_pos
-        }
+            index_to_pos,
+            index_labels: Vec::new()
+        } )
+    }
+
+    /// Build a table from records. Fails if a label is used more than once.
+    pub fn from_vecs( labels: Vec<String>, datatypes: Vec<FlexDataType>, data: Vec<FlexDataVector> ) -> Result<Self, &'static str> {
+        check_unique_labels( &labels )?;
+        Ok( Self::from_unique_vecs(labels, datatypes, data) )
     }
 
-    pub fn from_vecs( labels: Vec<String>, datatypes: Vec<FlexDataType>, data: Vec<FlexDataVector> ) -> Self {
+    /// Same as from_vecs, for labels known to be unique
+    fn from_unique_vecs( labels: Vec<String>, datatypes: Vec<FlexDataType>, data: Vec<FlexDataVector> ) -> Self {
         let mod_data : Vec<FlexDataVector> = data.into_iter()
             .map(|d| d.as_types(&datatypes))
             .collect();
@@ -66,16 +80,18 @@ impl FlexTable {
             label_to_pos.insert( l.to_string(), i);
         }
         Self{
-            iter_counter: 0,
             labels,
             datatypes,
             data: mod_data,
             label_to_pos,
-            index_to_pos
+            index_to_pos,
+            index_labels: Vec::new()
         }
     }
 
-    pub fn from_csv(text: &str, headers: Vec<String>, datatypes: Vec<FlexDataType>) -> Self {
+    /// Parse the given CSV headers as series of the given datatypes. Fails if a
+    /// header is used more than once.
+    pub fn from_csv(text: &str, headers: Vec<String>, datatypes: Vec<FlexDataType>) -> Result<Self, &'static str> {
         let mut filtered_text = text.to_string();
         filtered_text.retain(|c| c != '"');
 
@@ -87,19 +103,100 @@ impl FlexTable {
             .collect();
 
         let mut datavectors : Vec<FlexDataVector> = Vec::new();
-        let mut counter = 0;
-        for line in filtered_text.lines().skip(1) {
+        for (counter, line) in filtered_text.lines().skip(1).enumerate() {
             let tokens : Vec<&str> = line.split(',').collect();
             let data : Vec<FlexData> = header_positions.iter()
                 .enumerate()
                 .map(|(i,&k)| generate_flexdata_from_str( tokens[k], &datatypes[i] ) )
                 .collect();
             datavectors.push( FlexDataVector::new( FlexIndex::Uint(counter), data ) );
-            counter += 1;
         }
         Self::from_vecs( headers, datatypes, datavectors )
     }
 
+    /// Build a table from series with different indices. Records cover every
+    /// index found in any series, in order of first appearance, and values
+    /// missing from a series are NA.
+    pub fn align(series: Vec<FlexSeries>) -> Result<Self, &'static str> {
+        let labels : Vec<String> = series.iter().map(|s| s.get_label().to_string()).collect();
+        check_unique_labels( &labels )?;
+        let mut seen : HashSet<&FlexIndex> = HashSet::new();
+        let mut indices : Vec<&FlexIndex> = Vec::new();
+        for s in series.iter() {
+            for index in s.get_indices() {
+                if seen.insert(index) {
+                    indices.push(index);
+                }
+            }
+        }
+        let records : Vec<FlexDataVector> = indices.into_iter()
+            .map(|index| {
+                let data : Vec<FlexData> = series.iter()
+                    .map(|s| s.at(index).map_or(FlexData::NA, |fdp| fdp.get_data().clone()))
+                    .collect();
+                FlexDataVector::new(index.clone(), data)
+            })
+            .collect();
+        let datatypes : Vec<FlexDataType> = series.iter().map(|s| s.get_datatype().clone()).collect();
+        Ok( Self::from_unique_vecs(labels, datatypes, records) )
+    }
+
+    /// Build a table sharing the labels, datatypes and index levels of self
+    fn with_records(&self, records: Vec<FlexDataVector>) -> Self {
+        let mut table = Self::from_unique_vecs( self.labels.clone(), self.datatypes.clone(), records );
+        table.index_labels = self.index_labels.clone();
+        table
+    }
+
+    /// Positions of the labels, rejecting unknown or repeated labels
+    fn positions_of(&self, labels: &[&str]) -> Result<Vec<usize>, &'static str> {
+        let mut positions : Vec<usize> = Vec::new();
+        for &label in labels.iter() {
+            let pos = *self.label_to_pos.get(label).ok_or("Label not found")?;
+            if positions.contains( &pos ) {
+                return Err("Label used more than once");
+            }
+            positions.push( pos );
+        }
+        Ok(positions)
+    }
+
+    /// Values of the series matched to the records by index
+    fn align_series(&self, series: &FlexSeries) -> Vec<FlexData> {
+        self.data.iter()
+            .map(|v| {
+                series.at( v.get_index() )
+                    .map_or(FlexData::NA, |fdp| convert(fdp.get_data(), series.get_datatype()))
+            })
+            .collect()
+    }
+
+    /// Build a table keeping only the series at the given positions
+    fn project(&self, positions: &[usize], records: Vec<FlexDataVector>) -> Self {
+        let labels : Vec<String> = positions.iter().map(|&pos| self.labels[pos].clone()).collect();
+        let datatypes : Vec<FlexDataType> = positions.iter().map(|&pos| self.datatypes[pos].clone()).collect();
+        let mod_records : Vec<FlexDataVector> = records.into_iter()
+            .map(|v| {
+                let data : Vec<FlexData> = positions.iter().map(|&pos| v[pos].clone()).collect();
+                FlexDataVector::new(v.get_index().clone(), data)
+            })
+            .collect();
+        let mut table = Self::from_unique_vecs( labels, datatypes, mod_records );
+        table.index_labels = self.index_labels.clone();
+        table
+    }
+
+    fn rebuild_positions(&mut self) {
+        self.label_to_pos = self.labels.iter()
+            .enumerate()
+            .map(|(i,l)| (l.to_string(), i))
+            .collect();
+        self.index_to_pos = self.data.iter()
+            .enumerate()
+            .map(|(i,v)| (v.get_index().clone(), i))
+            .collect();
+    }
+
     pub fn to_csv(&self, filepath: &str) {
         let mut file = std::fs::File::create(filepath).expect("File creation failed");
         file.write_all(",".as_bytes()).expect("Writing failed");
@@ -107,11 +204,7 @@ impl FlexTable {
         file.write_all("\n".to_string().as_bytes()).expect("Writing failed");
         for i in 0..self.num_records() {
             let mut row : Vec<String> = Vec::new();
-            let cell = match self.data[i].get_index() {
-                FlexIndex::Uint(val) => format!("{}", val),
-                FlexIndex::Str(val) => val.clone()
-            };
-            row.push(cell);
+            row.push( format!("{}", self.data[i].get_index()) );
             for d in self.data[i].get_data() {
                 let cell = match d {
                     FlexData::Str(val) => val.clone(),
@@ -138,8 +231,13 @@ impl FlexTable {
         &self.datatypes
     }
 
+    /// Indices in record order
     pub fn get_indices(&self) -> Vec<FlexIndex> {
-        self.index_to_pos.keys().cloned().collect()
+        self.data.iter().map(|v| v.get_index().clone()).collect()
+    }
+
+    pub fn get_index_labels(&self) -> &Vec<String> {
+        &self.index_labels
     }
 
     pub fn num_records(&self) -> usize {
@@ -150,6 +248,21 @@ impl FlexTable {
         self.datatypes.len()
     }
 
+    // Iterating
+
+    pub fn iter(&self) -> std::slice::Iter<'_, FlexDataVector> {
+        self.data.iter()
+    }
+
+    /// Iterate over the indices and mutable records
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=(&FlexIndex, &mut [FlexData])> {
+        self.data.iter_mut().map(|v| v.parts_mut())
+    }
+
+    pub fn par_iter(&self) -> rayon::slice::Iter<'_, FlexDataVector> {
+        self.data.par_iter()
+    }
+
     // Selecting
 
     pub fn at(&self, index: &FlexIndex) -> Option<FlexDataVector> {
@@ -164,7 +277,87 @@ impl FlexTable {
         let records : Vec<FlexDataVector> = indices.into_iter()
             .filter_map(|index| self.at(&index))
             .collect();
-        Self::from_vecs( self.labels.clone(), self.datatypes.clone(),  records )
+        self.with_records( records )
+    }
+
+    pub fn select(&self, labels: &[&str]) -> Result<Self, &'static str> {
+        let positions = self.positions_of( labels )?;
+        Ok( self.project( &positions, self.data.clone() ) )
+    }
+
+    /// Records between positions start and end inclusive. Negative positions
+    /// count from the end and out of bounds positions are clamped.
+    pub fn range(&self, start: i32, end: i32) -> Self {
+        let n = self.num_records() as i64;
+        let resolve = |pos: i32| -> i64 { if pos >= 0 { pos as i64 } else { n + pos as i64 } };
+        let is = resolve(start).max(0);
+        let ie = resolve(end).min(n - 1);
+        let records : Vec<FlexDataVector> = if is <= ie {
+            self.data[is as usize..=ie as usize].to_vec()
+        } else {
+            Vec::new()
+        };
+        self.with_records( records )
+    }
+
+    /// Records whose index lies between start and end inclusive
+    pub fn range_index(&self, start: &FlexIndex, end: &FlexIndex) -> Self {
+        let records : Vec<FlexDataVector> = self.data.iter()
+            .filter(|v| v.get_index() >= start && v.get_index() <= end)
+            .cloned()
+            .collect();
+        self.with_records( records )
+    }
+
+    /// Records matching the indices restricted to the series matching the labels
+    pub fn loc(&self, indices: &[FlexIndex], labels: &[&str]) -> Result<Self, &'static str> {
+        let positions = self.positions_of( labels )?;
+        let records : Vec<FlexDataVector> = indices.iter()
+            .filter_map(|index| self.at(index))
+            .collect();
+        Ok( self.project( &positions, records ) )
+    }
+
+    pub fn head(&self, n: usize) -> Self {
+        self.with_records( self.data.iter().take(n).cloned().collect() )
+    }
+
+    pub fn tail(&self, n: usize) -> Self {
+        self.with_records( self.data[self.num_records().saturating_sub(n)..].to_vec() )
+    }
+
+    /// Random selection of n records without replacement, kept in table order.
+    /// The same seed always returns the same records.
+    pub fn sample(&self, n: usize, seed: u64) -> Self {
+        let mut positions : Vec<usize> = (0..self.num_records()).collect();
+        let k = n.min(positions.len());
+        let mut state = seed;
+        for i in 0..k {
+            let j = i + (splitmix64(&mut state) % (positions.len() - i) as u64) as usize;
+            positions.swap(i, j);
+        }
+        let mut selected = positions[..k].to_vec();
+        selected.sort_unstable();
+        self.with_records( selected.into_iter().map(|i| self.data[i].clone()).collect() )
+    }
+
+    /// Records whose leading index levels match the partial key
+    pub fn at_partial(&self, key: &[FlexIndex]) -> Self {
+        let records : Vec<FlexDataVector> = self.data.iter()
+            .filter(|v| v.get_index().starts_with(key))
+            .cloned()
+            .collect();
+        self.with_records( records )
+    }
+
+    /// Records whose index level named `level` matches the key
+    pub fn at_level(&self, level: &str, key: &FlexIndex) -> Result<Self, &'static str> {
+        let pos = self.index_labels.iter().position(|l| l == level).ok_or("Index level not found")?;
+        let records : Vec<FlexDataVector> = self.data.iter()
+            .filter(|v| v.get_index().get_level(pos) == Some(key))
+            .cloned()
+            .collect();
+        Ok( self.with_records( records ) )
     }
 
     pub fn extract_series(&self, labels: &[&str]) -> Vec<FlexSeries> {
@@ -197,46 +390,220 @@ impl FlexTable {
 
     // Modifiers
 
-    pub fn add_series(&mut self, series: FlexSeries) {
-        let adj_series = series.align_to( &self.get_indices() );
-        self.labels.push( adj_series.get_label().to_string() );
-        self.label_to_pos.insert( adj_series.get_label().to_string(), self.labels.len() - 1);
-        self.datatypes.push( adj_series.get_datatype().clone() );
+    /// Append a series, matching its values to the records by index.
+    /// Records missing from the series are set to NA.
+    pub fn add_series(&mut self, series: FlexSeries) -> Result<(), &'static str> {
+        if self.label_to_pos.contains_key( series.get_label() ) {
+            return Err("Label already used by a series");
+        }
+        let column = self.align_series( &series );
+        self.labels.push( series.get_label().to_string() );
+        self.label_to_pos.insert( series.get_label().to_string(), self.labels.len() - 1);
+        self.datatypes.push( series.get_datatype().clone() );
+        let mod_data : Vec<FlexDataVector> = self.data.iter()
+            .zip(column)
+            .map(|(dv,d)| {
+                let mut v = dv.get_data().clone();
+                v.push( d );
+                FlexDataVector::new(dv.get_index().clone(), v)
+            })
+            .collect();
+        self.data = mod_data;
+        Ok(())
+    }
+
+    /// Replace the series sharing the label of the new series
+    pub fn replace_series(&mut self, series: FlexSeries) -> Result<(), &'static str> {
+        let pos = *self.label_to_pos.get( series.get_label() ).ok_or("Label not found")?;
+        let column = self.align_series( &series );
+        self.datatypes[pos] = series.get_datatype().clone();
         let mod_data : Vec<FlexDataVector> = self.data.iter()
-            .zip(adj_series.get_data())
-            .map(|(dv,dp)| {
+            .zip(column)
+            .map(|(dv,d)| {
                 let mut v = dv.get_data().clone();
-                v.push( dp.clone() );
+                v[pos] = d;
                 FlexDataVector::new(dv.get_index().clone(), v)
             })
             .collect();
         self.data = mod_data;
+        Ok(())
+    }
+
+    pub fn drop(&mut self, labels: &[&str]) -> Result<(), &'static str> {
+        let dropped = self.positions_of( labels )?;
+        let positions : Vec<usize> = (0..self.num_series())
+            .filter(|pos| !dropped.contains(pos))
+            .collect();
+        *self = self.project( &positions, self.data.clone() );
+        Ok(())
+    }
+
+    pub fn rename(&mut self, label: &str, new_label: &str) -> Result<(), &'static str> {
+        self.rename_all( &[(label, new_label)] )
+    }
+
+    /// Rename several series at once from (label, new label) pairs
+    pub fn rename_all(&mut self, mapping: &[(&str, &str)]) -> Result<(), &'static str> {
+        let mut labels = self.labels.clone();
+        for &(label, new_label) in mapping.iter() {
+            let pos = *self.label_to_pos.get(label).ok_or("Label not found")?;
+            labels[pos] = new_label.to_string();
+        }
+        let unique : HashSet<&String> = labels.iter().collect();
+        if unique.len() != labels.len() {
+            return Err("Label used more than once");
+        }
+        self.labels = labels;
+        self.rebuild_positions();
+        Ok(())
+    }
+
+    /// Reorder the series following labels, which must list every label once
+    pub fn reorder(&mut self, labels: &[&str]) -> Result<(), &'static str> {
+        if labels.len() != self.num_series() {
+            return Err("Labels do not match the table series");
+        }
+        let positions = self.positions_of( labels )?;
+        *self = self.project( &positions, self.data.clone() );
+        Ok(())
     }
 
     pub fn remove_record(&mut self, k: usize) {
-        self.index_to_pos.remove( self.data[k].get_index() );
-        self.data.remove(k);
+        let removed = self.data.remove(k);
+        self.index_to_pos.remove( removed.get_index() );
+        for v in self.data[k..].iter() {
+            if let Some( pos ) = self.index_to_pos.get_mut( v.get_index() ) {
+                *pos -= 1;
+            }
+        }
     }
 
     pub fn remove_record_at(&mut self, index: &FlexIndex) {
         if let Some( &i ) = self.index_to_pos.get( index ) {
-            self.index_to_pos.remove( index );
-            self.data.remove(i);
+            self.remove_record(i);
         }
     }
 
-    pub fn set_index(&mut self, label: &str) {
-        let pos = self.label_to_pos.get(label).unwrap();
-        let mod_data : Vec<FlexDataVector> = self.data.iter()
+    pub fn set_index(&mut self, label: &str) -> Result<(), &'static str> {
+        self.set_multi_index( &[label] )
+    }
+
+    /// Move one or several series into the index. Several labels produce
+    /// a composite FlexIndex::Tuple with one level per label. The table is
+    /// left untouched if a label is unknown, a value can not be an index or
+    /// the resulting keys are not unique.
+    pub fn set_multi_index(&mut self, labels: &[&str]) -> Result<(), &'static str> {
+        let positions = self.positions_of( labels )?;
+        let mut indices : Vec<FlexIndex> = Vec::new();
+        for v in self.data.iter() {
+            let mut levels : Vec<FlexIndex> = positions.iter()
+                .map(|&pos| make_index_from_data( &v[pos] ))
+                .collect::<Result<Vec<FlexIndex>, &'static str>>()?;
+            indices.push( if levels.len() == 1 { levels.remove(0) } else { FlexIndex::Tuple(levels) } );
+        }
+        self.move_to_index( &positions, labels, indices )
+    }
+
+    /// Move a series of dates or timestamps into the index, parsing its values
+    /// with a chrono format string. Values holding a time are kept as
+    /// FlexIndex::DateTime in UTC, values holding only a date as FlexIndex::Date.
+    pub fn set_time_index(&mut self, label: &str, format: &str) -> Result<(), &'static str> {
+        let positions = self.positions_of( &[label] )?;
+        let indices : Vec<FlexIndex> = self.data.iter()
             .map(|v| {
+                let token = match &v[positions[0]] {
+                    FlexData::Str(val) => val.as_str(),
+                    _ => return Err("Only FlexData::Str can be parsed as dates")
+                };
+                NaiveDateTime::parse_from_str(token, format)
+                    .map(|dt| FlexIndex::DateTime(dt.and_utc()))
+                    .or_else(|_| NaiveDate::parse_from_str(token, format).map(FlexIndex::Date))
+                    .map_err(|_| "Value does not match the date format")
+            })
+            .collect::<Result<Vec<FlexIndex>, &'static str>>()?;
+        self.move_to_index( &positions, &[label], indices )
+    }
+
+    /// Drop the series at positions and key the records with indices
+    fn move_to_index(&mut self, positions: &[usize], labels: &[&str], indices: Vec<FlexIndex>) -> Result<(), &'static str> {
+        let unique : HashSet<&FlexIndex> = indices.iter().collect();
+        if unique.len() != indices.len() {
+            return Err("Index values are not unique");
+        }
+        let mut dropped = positions.to_vec();
+        dropped.sort_unstable_by(|a,b| b.cmp(a));
+        let mod_data : Vec<FlexDataVector> = self.data.iter()
+            .zip(indices)
+            .map(|(v,index)| {
                 let mut data = v.get_data().clone();
-                let index = make_index_from_data( &data[*pos] );
-                data.remove( *pos );
+                for &pos in dropped.iter() {
+                    data.remove( pos );
+                }
                 FlexDataVector::new(index, data)
             })
             .collect();
+        for &pos in dropped.iter() {
+            self.labels.remove( pos );
+            self.datatypes.remove( pos );
+        }
+        self.data = mod_data;
+        self.index_labels = labels.iter().map(|l| l.to_string()).collect();
+        self.rebuild_positions();
+        Ok(())
+    }
+
+    /// Move the index levels back into series placed at the front of the table
+    /// and replace the index with the record position. Dates and timestamps
+    /// become FlexData::Str in ISO 8601, which set_time_index parses back with
+    /// the formats "%Y-%m-%d" and "%+". Integers out of the range of FlexData
+    /// are rejected.
+    pub fn reset_index(&mut self) -> Result<(), &'static str> {
+        let num_levels = self.data.first().map(|v| v.get_index().num_levels()).unwrap_or( self.index_labels.len() );
+        let index_labels : Vec<String> = if self.index_labels.len() == num_levels {
+            self.index_labels.clone()
+        } else if num_levels == 1 {
+            vec![String::from("index")]
+        } else {
+            (0..num_levels).map(|i| format!("level_{}", i)).collect()
+        };
+        if index_labels.iter().any(|l| self.label_to_pos.contains_key(l)) {
+            return Err("Index level label already used by a series");
+        }
+        let mod_data : Vec<FlexDataVector> = self.data.iter()
+            .enumerate()
+            .map(|(i,v)| {
+                let mut data : Vec<FlexData> = (0..num_levels)
+                    .map(|level| v.get_index().get_level(level).map_or(Ok( FlexData::NA ), make_data_from_index))
+                    .collect::<Result<Vec<FlexData>, &'static str>>()?;
+                data.extend( v.get_data().iter().cloned() );
+                Ok( FlexDataVector::new(FlexIndex::Uint(i), data) )
+            })
+            .collect::<Result<Vec<FlexDataVector>, &'static str>>()?;
+        let index_datatypes : Vec<FlexDataType> = (0..num_levels)
+            .map(|level| {
+                mod_data.iter()
+                    .map(|v| derive_datatype( &v[level] ))
+                    .find(|datatype| datatype != &FlexDataType::NA)
+                    .unwrap_or(FlexDataType::NA)
+            })
+            .collect();
+        self.labels.splice(0..0, index_labels);
+        self.datatypes.splice(0..0, index_datatypes);
+        self.data = mod_data;
+        self.index_labels = Vec::new();
+        self.rebuild_positions();
+        Ok(())
+    }
+
+    /// Replace the index with the record position, discarding the current index
+    pub fn renumber(&mut self) {
+        let mod_data : Vec<FlexDataVector> = self.data.iter()
+            .enumerate()
+            .map(|(i,v)| FlexDataVector::new(FlexIndex::Uint(i), v.get_data().clone()))
+            .collect();
         self.data = mod_data;
-        self.label_to_pos.remove(label);
+        self.index_labels = Vec::new();
+        self.rebuild_positions();
     }
 
     // Filtering
@@ -255,7 +622,7 @@ impl FlexTable {
                 records.push( self.data[k].clone() );
             }
         }
-        Self::from_vecs( self.labels.clone(), self.datatypes.clone(),  records )
+        self.with_records( records )
     }
 
     pub fn filter_any(&self, labels: &[&str], f: impl Fn(&FlexData) -> bool) -> Self {
@@ -271,7 +638,7 @@ impl FlexTable {
                 records.push( self.data[k].clone() );
             }
         }
-        Self::from_vecs( self.labels.clone(), self.datatypes.clone(),  records )
+        self.with_records( records )
     }
 
     // NA Management
@@ -295,6 +662,262 @@ impl FlexTable {
         self.filter_all( labels.as_slice(), |x: &FlexData| x != &FlexData::NA )
     }
 
+    pub fn fill_na(&self, value: &FlexData) -> Self {
+        self.map_series(|s| s.fill_na(value))
+    }
+
+    /// Fill NA with a constant specific to each listed series
+    pub fn fill_na_series(&self, values: &[(&str, FlexData)]) -> Result<Self, &'static str> {
+        let labels : Vec<&str> = values.iter().map(|(label,_)| *label).collect();
+        let positions = self.positions_of( &labels )?;
+        let mut table = self.clone();
+        for (&pos, (_, value)) in positions.iter().zip(values.iter()) {
+            let mod_data : Vec<FlexDataVector> = table.data.iter()
+                .map(|v| {
+                    let mut data = v.get_data().clone();
+                    if data[pos] == FlexData::NA {
+                        data[pos] = convert(value, &self.datatypes[pos]);
+                    }
+                    FlexDataVector::new(v.get_index().clone(), data)
+                })
+                .collect();
+            table.data = mod_data;
+        }
+        Ok( table )
+    }
+
+    pub fn ffill(&self, limit: Option<usize>) -> Self {
+        self.map_series(|s| s.ffill(limit))
+    }
+
+    pub fn bfill(&self, limit: Option<usize>) -> Self {
+        self.map_series(|s| s.bfill(limit))
+    }
+
+    /// Interpolate the numeric series, see FlexSeries::interpolate
+    pub fn interpolate(&self, use_index: bool) -> Result<Self, &'static str> {
+        let series = self.extract_series( &self.numeric_labels() )
+            .par_iter()
+            .map(|s| s.interpolate(use_index))
+            .collect::<Result<Vec<FlexSeries>, _>>()?;
+        let mut table = self.clone();
+        for s in series.into_iter() {
+            table.replace_series(s)?;
+        }
+        Ok( table )
+    }
+
+    pub fn fill_na_mean(&self) -> Self {
+        self.map_numeric_series(|s| s.fill_na_mean())
+    }
+
+    pub fn fill_na_median(&self) -> Self {
+        self.map_numeric_series(|s| s.fill_na_median())
+    }
+
+    /// Apply f to each value of the listed series, the results being converted to datatype
+    pub fn apply_as(&self, labels: &[&str], datatype: &FlexDataType, f: impl Fn(&FlexData) -> FlexData + Sync) -> Result<Self, &'static str> {
+        self.positions_of( labels )?;
+        Ok( self.map_labeled_series(labels, |s| s.apply_as(datatype, &f)) )
+    }
+
+    fn map_series(&self, f: impl Fn(&FlexSeries) -> FlexSeries + Sync) -> Self {
+        let labels : Vec<&str> = self.labels.iter().map(|l| l.as_str()).collect();
+        self.map_labeled_series(&labels, f)
+    }
+
+    fn map_numeric_series(&self, f: impl Fn(&FlexSeries) -> FlexSeries + Sync) -> Self {
+        self.map_labeled_series(&self.numeric_labels(), f)
+    }
+
+    fn numeric_labels(&self) -> Vec<&str> {
+        self.labels.iter()
+            .zip(self.datatypes.iter())
+            .filter(|(_,t)| is_numeric(t))
+            .map(|(l,_)| l.as_str())
+            .collect()
+    }
+
+    fn map_labeled_series(&self, labels: &[&str], f: impl Fn(&FlexSeries) -> FlexSeries + Sync) -> Self {
+        let series : Vec<FlexSeries> = self.extract_series(labels)
+            .par_iter()
+            .map(&f)
+            .collect();
+        let mut table = self.clone();
+        for s in series.into_iter() {
+            table.replace_series(s).expect("Label not found");
+        }
+        table
+    }
+
+    // Statistics
+
+    /// Summary table with one series per series of self. Numeric series are
+    /// described by their count, NA count, mean, std, min, quartiles and max,
+    /// other series by their count, NA count, number of unique values, most
+    /// frequent value and its frequency.
+    pub fn describe(&self) -> Self {
+        let stats = ["count", "na", "mean", "std", "min", "25%", "50%", "75%", "max", "unique", "top", "freq"];
+        let labels : Vec<&str> = self.labels.iter().map(|l| l.as_str()).collect();
+        let columns : Vec<Vec<FlexData>> = self.extract_series(&labels)
+            .par_iter()
+            .map(|s| {
+                let count = s.count();
+                let na = s.get_size() - count;
+                let mut column = vec![FlexData::Uint(count as u32), FlexData::Uint(na as u32)];
+                let to_flexdata = |x: Option<f64>| x.map_or(FlexData::NA, FlexData::Dbl);
+                if is_numeric( s.get_datatype() ) {
+                    column.push( to_flexdata( s.mean() ) );
+                    column.push( to_flexdata( s.std(true) ) );
+                    column.push( s.min().map_or(FlexData::NA, |x| convert(&x, &FlexDataType::Dbl)) );
+                    for q in [0.25, 0.5, 0.75].iter() {
+                        column.push( to_flexdata( s.quantile(*q, &QuantileMethod::Linear) ) );
+                    }
+                    column.push( s.max().map_or(FlexData::NA, |x| convert(&x, &FlexDataType::Dbl)) );
+                    column.extend( vec![FlexData::NA; 3] );
+                } else {
+                    let counts = s.value_counts();
+                    column.extend( vec![FlexData::NA; 7] );
+                    column.push( FlexData::Uint(counts.len() as u32) );
+                    match counts.first() {
+                        Some( (top, freq) ) => {
+                            column.push( convert(top, &FlexDataType::Str) );
+                            column.push( FlexData::Uint(*freq as u32) );
+                        },
+                        None => column.extend( vec![FlexData::NA; 2] )
+                    }
+                }
+                column
+            })
+            .collect();
+        let datatypes : Vec<FlexDataType> = self.datatypes.iter()
+            .map(|t| if is_numeric(t) { FlexDataType::Dbl } else { FlexDataType::Str })
+            .collect();
+        let records : Vec<FlexDataVector> = stats.iter()
+            .enumerate()
+            .map(|(i,stat)| {
+                let data : Vec<FlexData> = columns.iter().map(|c| c[i].clone()).collect();
+                FlexDataVector::new( FlexIndex::Str(stat.to_string()), data )
+            })
+            .filter(|v| v.get_data().iter().any(|x| x != &FlexData::NA))
+            .collect();
+        Self::from_unique_vecs( self.labels.clone(), datatypes, records )
+    }
+
+    /// Correlation matrix of the numeric series, computed pairwise on the
+    /// records where neither value is NA
+    pub fn corr(&self, method: &CorrelationMethod) -> Self {
+        self.pairwise_matrix(|xs, ys| stats::correlation(xs, ys, method))
+    }
+
+    /// Covariance matrix of the numeric series, computed pairwise on the
+    /// records where neither value is NA
+    pub fn cov(&self, is_sample: bool) -> Self {
+        self.pairwise_matrix(|xs, ys| stats::covariance(xs, ys, is_sample))
+    }
+
+    fn pairwise_matrix(&self, f: impl Fn(&[f64], &[f64]) -> Option<f64> + Sync) -> Self {
+        let positions : Vec<usize> = (0..self.num_series())
+            .filter(|&pos| is_numeric(&self.datatypes[pos]))
+            .collect();
+        let columns : Vec<Vec<Option<f64>>> = positions.par_iter()
+            .map(|&pos| {
+                self.data.iter()
+                    .map(|v| f64::try_from( &convert(&v[pos], &FlexDataType::Dbl) ).ok())
+                    .collect()
+            })
+            .collect();
+        let n = positions.len();
+        let pairs : Vec<(usize,usize)> = (0..n).flat_map(|i| (i..n).map(move |j| (i,j))).collect();
+        let values : HashMap<(usize,usize), FlexData> = pairs.into_par_iter()
+            .map(|(i,j)| {
+                let (xs, ys) : (Vec<f64>, Vec<f64>) = columns[i].iter()
+                    .zip(columns[j].iter())
+                    .filter_map(|(x,y)| x.zip(*y))
+                    .unzip();
+                ((i,j), f(&xs, &ys).map_or(FlexData::NA, FlexData::Dbl))
+            })
+            .collect();
+        let labels : Vec<String> = positions.iter().map(|&pos| self.labels[pos].clone()).collect();
+        let records : Vec<FlexDataVector> = (0..n)
+            .map(|i| {
+                let data : Vec<FlexData> = (0..n).map(|j| values[&(i.min(j), i.max(j))].clone()).collect();
+                FlexDataVector::new( FlexIndex::Str(labels[i].clone()), data )
+            })
+            .collect();
+        Self::from_unique_vecs( labels, vec![FlexDataType::Dbl; n], records )
+    }
+
+    // Joining
+
+    /// Append the series of other to each record, taken from the record of other
+    /// whose `on` value is the closest in the given direction, within tolerance
+    /// if any. Records without a match get NA. The `on` series of other is not
+    /// appended and the other labels must not be used in self. Nearest matches
+    /// and tolerances require numeric `on` series.
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn merge_asof(&self, other: &Self, on: &str, direction: &AsofDirection, tolerance: Option<f64>) -> Result<Self, &'static str> {
+        let key_pos = *self.label_to_pos.get(on).ok_or("Label not found")?;
+        let other_key_pos = *other.label_to_pos.get(on).ok_or("Label not found")?;
+        let appended : Vec<usize> = (0..other.labels.len()).filter(|&pos| pos != other_key_pos).collect();
+        if appended.iter().any(|&pos| self.label_to_pos.contains_key( &other.labels[pos] )) {
+            return Err("Label already used by a series");
+        }
+        let numeric = is_numeric(&self.datatypes[key_pos]) && is_numeric(&other.datatypes[other_key_pos]);
+        if !numeric && (tolerance.is_some() || direction == &AsofDirection::Nearest) {
+            return Err("Series is not numeric");
+        }
+        // Keys of other in ascending order, in the datatype of the keys of self
+        let key_type = &self.datatypes[key_pos];
+        let mut keys : Vec<(FlexData, usize)> = other.data.iter()
+            .enumerate()
+            .map(|(i,v)| (convert(&v[other_key_pos], key_type), i))
+            .filter(|(key,_)| key != &FlexData::NA)
+            .collect();
+        keys.sort_by(|a,b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        let distance = |a: &FlexData, b: &FlexData| {
+            let a = f64::try_from( &convert(a, &FlexDataType::Dbl) ).unwrap_or(f64::NAN);
+            let b = f64::try_from( &convert(b, &FlexDataType::Dbl) ).unwrap_or(f64::NAN);
+            (a - b).abs()
+        };
+
+        let records : Vec<FlexDataVector> = self.data.iter()
+            .map(|v| {
+                let key = &v[key_pos];
+                let after = keys.partition_point(|(k,_)| k < key);
+                let before = keys.partition_point(|(k,_)| k <= key).checked_sub(1);
+                let after = Some( after ).filter(|&pos| pos < keys.len());
+                let matched = match direction {
+                    AsofDirection::Backward => before,
+                    AsofDirection::Forward => after,
+                    AsofDirection::Nearest => match (before, after) {
+                        (Some( b ), Some( a )) => if distance(&keys[a].0, key) < distance(&keys[b].0, key) { Some( a ) } else { Some( b ) },
+                        (b, a) => b.or(a)
+                    }
+                };
+                let matched = matched
+                    .filter(|_| key != &FlexData::NA)
+                    .filter(|&pos| tolerance.map_or(true, |tol| distance(&keys[pos].0, key) <= tol))
+                    .map(|pos| &other.data[keys[pos].1]);
+                let mut data = v.get_data().clone();
+                for &pos in appended.iter() {
+                    data.push( matched.map_or(FlexData::NA, |w| w[pos].clone()) );
+                }
+                FlexDataVector::new(v.get_index().clone(), data)
+            })
+            .collect();
+
+        let mut labels = self.labels.clone();
+        let mut datatypes = self.datatypes.clone();
+        for &pos in appended.iter() {
+            labels.push( other.labels[pos].clone() );
+            datatypes.push( other.datatypes[pos].clone() );
+        }
+        let mut table = Self::from_unique_vecs( labels, datatypes, records );
+        table.index_labels = self.index_labels.clone();
+        Ok(table)
+    }
+
     // n-ary operation
 
     pub fn nary_apply(&self, label: &str, datatype: FlexDataType, labels: &[&str], f: impl Fn(&[&FlexData]) -> FlexData) -> FlexSeries {
@@ -319,7 +942,34 @@ impl FlexTable {
         } else {
             data.sort_by(|a,b| b[*pos].partial_cmp(&a[*pos]).unwrap() );
         }
-        FlexTable::from_vecs(self.labels.clone(), self.datatypes.clone(), data)
+        self.with_records( data )
+    }
+
+    /// Records ordered by index
+    pub fn sort_index(&self, ascending: bool) -> Self {
+        let mut data = self.data.clone();
+        if ascending {
+            data.sort_by(|a,b| a.get_index().cmp(b.get_index()) );
+        } else {
+            data.sort_by(|a,b| b.get_index().cmp(a.get_index()) );
+        }
+        self.with_records( data )
+    }
+
+    /// Records at the given indices in that order, fill being used for every
+    /// value of the indices not found. Duplicated indices are rejected.
+    pub fn reindex(&self, indices: &[FlexIndex], fill: &FlexData) -> Result<Self, &'static str> {
+        let unique : HashSet<&FlexIndex> = indices.iter().collect();
+        if unique.len() != indices.len() {
+            return Err("Index values are not unique");
+        }
+        let records : Vec<FlexDataVector> = indices.iter()
+            .map(|index| match self.index_to_pos.get( index ) {
+                Some( &pos ) => self.data[pos].clone(),
+                None => FlexDataVector::new( index.clone(), vec![fill.clone(); self.num_series()] )
+            })
+            .collect();
+        Ok( self.with_records( records ) )
     }
 
     // grouping 
@@ -333,15 +983,12 @@ impl FlexTable {
         if series.len() == 1 {
             // Define value set
             let mut value_set : HashMap<String, Vec<FlexIndex>> = HashMap::new();
-            for k in 0..series[0].get_size() {
-                let fdp = series[0][k].clone();
-                let val : String = String::try_from( &convert(fdp.get_data(), &FlexDataType::Str) )
+            for (index, data) in series[0].get_indices().into_iter().zip(series[0].get_data()) {
+                let val : String = String::try_from( &convert(data, &FlexDataType::Str) )
                     .expect("Value not convertible to String");
-                if let Some( v ) = value_set.get_mut( &val ) {
-                    v.push( fdp.get_index().clone() );
-                } else {
-                    value_set.insert( val, Vec::<FlexIndex>::new() );
-                }
+                value_set.entry( val )
+                    .or_default()
+                    .push( index.clone() );
             }
 
             // Build subsets
@@ -367,6 +1014,28 @@ impl FlexTable {
         res
     }
 
+    /// Group records by the values of one or several index levels. Grouping
+    /// on several levels produces FlexIndex::Tuple keys. Fails if a level is
+    /// not an index level.
+    pub fn group_by_levels(table: &Self, levels: &[&str]) -> Result<HashMap<FlexIndex, Self>, &'static str> {
+        let positions = levels.iter()
+            .map(|&level| table.index_labels.iter().position(|l| l == level).ok_or("Index level not found"))
+            .collect::<Result<Vec<usize>, _>>()?;
+        let mut records : HashMap<FlexIndex, Vec<FlexDataVector>> = HashMap::new();
+        for v in table.data.iter() {
+            let mut key = positions.iter()
+                .map(|&pos| v.get_index().get_level(pos).cloned().ok_or("Index level not found"))
+                .collect::<Result<Vec<FlexIndex>, _>>()?;
+            let key = if key.len() == 1 { key.remove(0) } else { FlexIndex::Tuple(key) };
+            records.entry( key )
+                .or_default()
+                .push( v.clone() );
+        }
+        Ok( records.into_par_iter()
+            .map(|(k,v)| (k, table.with_records(v)))
+            .collect() )
+    }
+
     // pretty print
 
     pub fn print(&self, max_size: Option<usize>) {
@@ -395,11 +1064,7 @@ impl FlexTable {
             let mut record_cells : Vec<Cell> = Vec::new();
             for j in 0..self.num_series() {
                 if j == 0 {
-                    let cell = match self.data[i].get_index() {
-                        FlexIndex::Uint(val) => Cell::new( format!("{}", val).as_str() ),
-                        FlexIndex::Str(val) => Cell::new( val.as_str() )
-                    };
-                    record_cells.push(cell);
+                    record_cells.push( Cell::new( format!("{}", self.data[i].get_index()).as_str() ) );
                 }
                 let cell = match &self.data[i].get_data()[j] {
                     FlexData::Str(val) => Cell::new( val.as_str() ),
@@ -418,6 +1083,26 @@ impl FlexTable {
     }
 }
 
+fn check_unique_labels(labels: &[String]) -> Result<(), &'static str> {
+    let unique_labels : HashSet<&String> = labels.iter().collect();
+    if unique_labels.len() < labels.len() {
+        return Err("Label used more than once");
+    }
+    Ok(())
+}
+
+fn is_numeric(datatype: &FlexDataType) -> bool {
+    matches!(datatype, FlexDataType::Dbl | FlexDataType::Uint | FlexDataType::Int)
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 //Implement [] operator
 
 impl Index<usize> for FlexTable {
@@ -427,17 +1112,20 @@ impl Index<usize> for FlexTable {
     }
 }
 
-impl Iterator for FlexTable {
+impl IntoIterator for FlexTable {
     type Item = FlexDataVector;
-    
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.iter_counter < self.num_records() {
-            let dv = self.data[self.iter_counter].clone();
-            self.iter_counter += 1;
-            Some( dv )
-        } else {
-            self.iter_counter = 0;
-            None
-        }
+    type IntoIter = std::vec::IntoIter<FlexDataVector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FlexTable {
+    type Item = &'a FlexDataVector;
+    type IntoIter = std::slice::Iter<'a, FlexDataVector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
\ No newline at end of file
@@ -1,11 +1,244 @@
+use std::convert::TryFrom;
+use std::fmt;
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::TimeIndex;
+
+/// Key of a record. Values of the same variant are ordered naturally,
+/// so tables keyed by dates or timestamps sort chronologically.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FlexIndex {
     Str(String),
-    Uint(usize)
+    Uint(usize),
+    Int(i64),
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+    Tuple(Vec<FlexIndex>)
+}
+
+impl FlexIndex {
+
+    /// Number of levels in the index (1 unless it is a Tuple)
+    pub fn num_levels(&self) -> usize {
+        match self {
+            FlexIndex::Tuple(levels) => levels.len(),
+            _ => 1
+        }
+    }
+
+    /// Get the value of the index at a given level
+    pub fn get_level(&self, level: usize) -> Option<&FlexIndex> {
+        match self {
+            FlexIndex::Tuple(levels) => levels.get(level),
+            _ => if level == 0 { Some(self) } else { None }
+        }
+    }
+
+    /// Check whether the leading levels of the index match the partial key
+    pub fn starts_with(&self, key: &[FlexIndex]) -> bool {
+        key.len() <= self.num_levels() && key.iter()
+            .enumerate()
+            .all(|(i,k)| self.get_level(i) == Some(k))
+    }
+
+    /// Milliseconds since the Unix epoch of a Date or DateTime index
+    pub fn as_millis(&self) -> Option<i64> {
+        match self {
+            FlexIndex::Date(val) => Some( val.to_millis() ),
+            FlexIndex::DateTime(val) => Some( val.to_millis() ),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for FlexIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlexIndex::Str(val) => write!(f, "{}", val),
+            FlexIndex::Uint(val) => write!(f, "{}", val),
+            FlexIndex::Int(val) => write!(f, "{}", val),
+            FlexIndex::Date(val) => write!(f, "{}", val),
+            FlexIndex::DateTime(val) => write!(f, "{}", val.to_rfc3339()),
+            FlexIndex::Tuple(levels) => {
+                let tokens : Vec<String> = levels.iter()
+                    .map(|level| format!("{}", level))
+                    .collect();
+                write!(f, "({})", tokens.join(", "))
+            }
+        }
+    }
+}
+
+impl From<usize> for FlexIndex {
+    fn from(value: usize) -> FlexIndex {
+        FlexIndex::Uint(value)
+    }
+}
+
+impl From<i64> for FlexIndex {
+    fn from(value: i64) -> FlexIndex {
+        FlexIndex::Int(value)
+    }
+}
+
+impl From<String> for FlexIndex {
+    fn from(value: String) -> FlexIndex {
+        FlexIndex::Str(value)
+    }
+}
+
+impl From<&str> for FlexIndex {
+    fn from(value: &str) -> FlexIndex {
+        FlexIndex::Str(value.to_string())
+    }
+}
+
+impl From<DateTime<Utc>> for FlexIndex {
+    fn from(value: DateTime<Utc>) -> FlexIndex {
+        FlexIndex::DateTime(value)
+    }
+}
+
+impl From<NaiveDate> for FlexIndex {
+    fn from(value: NaiveDate) -> FlexIndex {
+        FlexIndex::Date(value)
+    }
+}
+
+impl TryFrom<&FlexIndex> for usize {
+    type Error = &'static str;
+    fn try_from(value: &FlexIndex) -> Result<Self, Self::Error> {
+        match value {
+            FlexIndex::Uint(v) => Ok(*v),
+            _ => Err("Only FlexIndex::Uint can be extracted to usize")
+        }
+    }
+}
+
+impl TryFrom<&FlexIndex> for i64 {
+    type Error = &'static str;
+    fn try_from(value: &FlexIndex) -> Result<Self, Self::Error> {
+        match value {
+            FlexIndex::Int(v) => Ok(*v),
+            FlexIndex::Uint(v) => i64::try_from(*v).map_err(|_| "FlexIndex::Uint is too large for i64"),
+            _ => Err("Only FlexIndex::Int and FlexIndex::Uint can be extracted to i64")
+        }
+    }
+}
+
+impl TryFrom<&FlexIndex> for String {
+    type Error = &'static str;
+    fn try_from(value: &FlexIndex) -> Result<Self, Self::Error> {
+        match value {
+            FlexIndex::Str(v) => Ok(v.to_string()),
+            _ => Err("Only FlexIndex::Str can be extracted to String")
+        }
+    }
+}
+
+impl TryFrom<&FlexIndex> for DateTime<Utc> {
+    type Error = &'static str;
+    fn try_from(value: &FlexIndex) -> Result<Self, Self::Error> {
+        match value {
+            FlexIndex::DateTime(v) => Ok(*v),
+            FlexIndex::Str(v) => DateTime::parse_from_rfc3339(v)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|_| "FlexIndex::Str is not an RFC 3339 date and time"),
+            _ => Err("Only FlexIndex::DateTime and FlexIndex::Str can be extracted to DateTime")
+        }
+    }
+}
+
+impl TryFrom<&FlexIndex> for NaiveDate {
+    type Error = &'static str;
+    fn try_from(value: &FlexIndex) -> Result<Self, Self::Error> {
+        match value {
+            FlexIndex::Date(v) => Ok(*v),
+            FlexIndex::Str(v) => v.parse::<NaiveDate>().map_err(|_| "FlexIndex::Str is not an ISO 8601 date"),
+            _ => Err("Only FlexIndex::Date and FlexIndex::Str can be extracted to NaiveDate")
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum FlexIndexType {
     Str,
-    Uint
+    Uint,
+    Int,
+    Date,
+    DateTime,
+    Tuple
+}
+
+/// Interpolation used when a quantile falls between two values
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum QuantileMethod {
+    Linear,
+    Lower,
+    Higher,
+    Nearest,
+    Midpoint
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum CorrelationMethod {
+    Pearson,
+    Spearman,
+    Kendall
+}
+
+/// Rank given to tied values
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum RankMethod {
+    Average,
+    Min,
+    Max,
+    Dense,
+    First
+}
+
+/// Decay of exponentially weighted statistics
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Decay {
+    Alpha(f64),
+    Span(f64),
+    HalfLife(f64),
+    CenterOfMass(f64)
+}
+
+impl Decay {
+    /// Smoothing factor in (0,1], rejecting an alpha outside (0,1], a span
+    /// below 1, a non-positive half-life or a negative center of mass
+    pub fn alpha(&self) -> Result<f64, &'static str> {
+        match self {
+            Decay::Alpha(alpha) if *alpha > 0.0 && *alpha <= 1.0 => Ok( *alpha ),
+            Decay::Span(span) if *span >= 1.0 => Ok( 2.0 / (span + 1.0) ),
+            Decay::HalfLife(half_life) if *half_life > 0.0 => Ok( 1.0 - (0.5f64.ln() / half_life).exp() ),
+            Decay::CenterOfMass(com) if *com >= 0.0 => Ok( 1.0 / (1.0 + com) ),
+            _ => Err("Decay parameter out of range")
+        }
+    }
+}
+
+/// Side of a time bucket
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right
+}
+
+/// Side on which an as-of lookup searches for the closest key
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum AsofDirection {
+    Backward,
+    Forward,
+    Nearest
+}
+
+/// Indices kept when combining two series: those found in both, those of the
+/// left series, or those found in either
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Alignment {
+    Inner,
+    Left,
+    Outer
 }
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
-use crate::{FlexData, FlexIndex, FlexDataType};
+use std::convert::TryFrom;
+use crate::{math, FlexData, FlexIndex, FlexDataType};
 
 pub fn extract_csv_headers(text: &str) -> Vec<String> {
     let buffer : Vec<&str> = text.lines().take(1).collect();
@@ -29,27 +30,76 @@ pub fn generate_flexdata_from_str(token: &str, datatype: &FlexDataType) -> FlexD
     }
 }
 
-pub fn make_data_from_index(index: &FlexIndex) -> FlexData {
+/// Value of an index level. Dates and timestamps are written in ISO 8601, as
+/// parsed by the chrono formats "%Y-%m-%d" and "%+" respectively.
+pub fn make_data_from_index(index: &FlexIndex) -> Result<FlexData, &'static str> {
     match index {
-        FlexIndex::Uint(val) => FlexData::Uint(*val as u32),
-        FlexIndex::Str(val) => FlexData::Str(val.to_string())
+        FlexIndex::Uint(val) => u32::try_from(*val).map(FlexData::Uint).map_err(|_| "Index value out of the range of FlexData"),
+        FlexIndex::Int(val) => i32::try_from(*val).map(FlexData::Int).map_err(|_| "Index value out of the range of FlexData"),
+        FlexIndex::Str(val) => Ok( FlexData::Str(val.to_string()) ),
+        FlexIndex::Date(_) | FlexIndex::DateTime(_) => Ok( FlexData::Str(format!("{}", index)) ),
+        FlexIndex::Tuple(_) => Err("Index tuples can not be converted to FlexData")
     }
 }
 
-pub fn make_index_from_data(data: &FlexData) -> FlexIndex {
+/// Index level of a value. FlexData::Char is rejected as it would come back
+/// as FlexData::Str from make_data_from_index.
+pub fn make_index_from_data(data: &FlexData) -> Result<FlexIndex, &'static str> {
     match data {
-        FlexData::Uint(val) => FlexIndex::Uint(*val as usize),
-        FlexData::Int(val) => FlexIndex::Uint(*val as usize),
-        FlexData::Char(val) => FlexIndex::Str(format!("{}", val)),
-        FlexData::Str(val) => FlexIndex::Str(val.to_string()),
-        _ => panic!("FlexData::NA and FlexData::Dbl can not be indices")
+        FlexData::Uint(val) => Ok( FlexIndex::Uint(*val as usize) ),
+        FlexData::Int(val) => Ok( FlexIndex::Int(*val as i64) ),
+        FlexData::Str(val) => Ok( FlexIndex::Str(val.to_string()) ),
+        _ => Err("FlexData::NA, FlexData::Dbl and FlexData::Char can not be indices")
     }
 }
 
+pub fn index_as_f64(index: &FlexIndex) -> Option<f64> {
+    match index {
+        FlexIndex::Uint(val) => Some( *val as f64 ),
+        FlexIndex::Int(val) => Some( *val as f64 ),
+        _ => index.as_millis().map(|millis| millis as f64)
+    }
+}
+
+/// Position of the value lagged by n positions, leading when n is negative
+pub fn shift_position(pos: usize, n: i32, size: usize) -> Option<usize> {
+    let target = pos as i64 - n as i64;
+    if target >= 0 && (target as usize) < size { Some( target as usize ) } else { None }
+}
+
+/// Indices of first also found in other, in the order of first
 pub fn index_intersection(first: Vec<&FlexIndex>, other: Vec<&FlexIndex>) -> Vec<FlexIndex> {
-    let set1 : HashSet<FlexIndex> = first.into_iter().cloned().collect();
-    let set2 : HashSet<FlexIndex> = other.into_iter().cloned().collect();
-    set1.intersection(&set2).cloned().collect()
+    let set2 : HashSet<&FlexIndex> = other.into_iter().collect();
+    first.into_iter()
+        .filter(|index| set2.contains(index))
+        .cloned()
+        .collect()
+}
+
+/// Indices of first followed by the indices of other not found in first
+pub fn index_union(first: Vec<&FlexIndex>, other: Vec<&FlexIndex>) -> Vec<FlexIndex> {
+    let mut seen : HashSet<&FlexIndex> = HashSet::new();
+    first.into_iter()
+        .chain(other)
+        .filter(|index| seen.insert(index))
+        .cloned()
+        .collect()
+}
+
+/// Indices of first not found in other, in the order of first
+pub fn index_difference(first: Vec<&FlexIndex>, other: Vec<&FlexIndex>) -> Vec<FlexIndex> {
+    let set2 : HashSet<&FlexIndex> = other.into_iter().collect();
+    first.into_iter()
+        .filter(|index| !set2.contains(index))
+        .cloned()
+        .collect()
+}
+
+/// Indices of first not found in other followed by the indices of other not found in first
+pub fn index_symmetric_difference(first: Vec<&FlexIndex>, other: Vec<&FlexIndex>) -> Vec<FlexIndex> {
+    let mut res = index_difference(first.clone(), other.clone());
+    res.extend( index_difference(other, first) );
+    res
 }
 
 pub fn convert(x: &FlexData, datatype: &FlexDataType) -> FlexData {
@@ -98,37 +148,22 @@ pub fn convert(x: &FlexData, datatype: &FlexDataType) -> FlexData {
     }
 }
 
+/// Same as math::inverse, so integers are promoted to Dbl and a zero value
+/// gives NA
 pub fn inverse(x: &FlexData) -> FlexData {
-    match x {
-        FlexData::Dbl(val) => {
-            if val != &0f64 {
-                FlexData::Dbl(1.0 / val)
-            } else {
-                FlexData::NA
-            }
-        },
-        _ => FlexData::NA
-    }
+    math::inverse(x)
 }
 
+/// Same as math::ln, so integers are promoted to Dbl and values which are
+/// not positive give NA
 pub fn ln(x: &FlexData) -> FlexData {
-    match x {
-        FlexData::Dbl(val) => {
-            if val > &0f64 {
-                FlexData::Dbl( val.ln() )
-            } else {
-                FlexData::NA
-            }
-        },
-        _ => FlexData::NA
-    }
+    math::ln(x)
 }
 
+/// Same as math::exp, so integers are promoted to Dbl and overflows give NA
+/// rather than infinity
 pub fn exp(x: &FlexData) -> FlexData {
-    match x {
-        FlexData::Dbl(val) => FlexData::Dbl( val.exp() ),
-        _ => FlexData::NA
-    }
+    math::exp(x)
 }
 
 pub fn sum(v: Vec<FlexData>) -> FlexData {
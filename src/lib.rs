@@ -8,7 +8,14 @@ mod series;
 mod flexdatapoint;
 mod flexdata;
 mod globals;
+mod stats;
+mod numeric;
+mod timeindex;
+mod rolling;
+mod ewm;
+mod resample;
 pub mod helper;
+pub mod math;
 
 pub use self::flexdata::{FlexData, FlexDataType};
 pub use self::flexseries::FlexSeries;
@@ -17,4 +24,9 @@ pub use self::datapoint::DataPoint;
 pub use self::flexdatavector::FlexDataVector;
 pub use self::series::Series;
 pub use self::flexdatapoint::FlexDataPoint;
-pub use self::globals::{ FlexIndex, FlexIndexType};
\ No newline at end of file
+pub use self::globals::{ FlexIndex, FlexIndexType, QuantileMethod, CorrelationMethod, RankMethod, Decay, Side, AsofDirection, Alignment};
+pub use self::numeric::{Numeric, CheckedDiv};
+pub use self::timeindex::{TimeIndex, Interval};
+pub use self::rolling::{Rollable, Rolling};
+pub use self::ewm::Ewm;
+pub use self::resample::{Resampler, Ohlc};
@@ -0,0 +1,126 @@
+//! Math functions on FlexData, to be applied with `FlexSeries::apply_as` or
+//! `FlexTable::apply_as`. FlexData::Uint and FlexData::Int are promoted to
+//! FlexData::Dbl, other variants and domain errors give FlexData::NA.
+
+use crate::FlexData;
+
+fn to_f64(x: &FlexData) -> Option<f64> {
+    match x {
+        FlexData::Dbl(val) => Some( *val ),
+        FlexData::Uint(val) => Some( *val as f64 ),
+        FlexData::Int(val) => Some( *val as f64 ),
+        _ => None
+    }
+}
+
+fn unary(x: &FlexData, f: impl Fn(f64) -> f64) -> FlexData {
+    to_f64(x)
+        .map(f)
+        .filter(|y| y.is_finite())
+        .map_or(FlexData::NA, FlexData::Dbl)
+}
+
+/// Apply f to x scaled by 10^digits and scale the result back, x being kept
+/// as is when scaling it overflows
+fn at_digits(x: &FlexData, digits: i32, f: impl Fn(f64) -> f64) -> FlexData {
+    let scale = 10f64.powi(digits);
+    unary(x, |v| {
+        let scaled = v * scale;
+        if scaled.is_finite() { f(scaled) / scale } else { v }
+    })
+}
+
+pub fn abs(x: &FlexData) -> FlexData {
+    unary(x, f64::abs)
+}
+
+/// -1, 0 or 1 depending on the sign of x
+pub fn sign(x: &FlexData) -> FlexData {
+    unary(x, |v| if v == 0.0 { 0.0 } else { v.signum() })
+}
+
+pub fn sqrt(x: &FlexData) -> FlexData {
+    unary(x, f64::sqrt)
+}
+
+pub fn pow(x: &FlexData, exponent: f64) -> FlexData {
+    unary(x, |v| v.powf(exponent))
+}
+
+pub fn exp(x: &FlexData) -> FlexData {
+    unary(x, f64::exp)
+}
+
+pub fn ln(x: &FlexData) -> FlexData {
+    unary(x, f64::ln)
+}
+
+pub fn log10(x: &FlexData) -> FlexData {
+    unary(x, f64::log10)
+}
+
+pub fn log2(x: &FlexData) -> FlexData {
+    unary(x, f64::log2)
+}
+
+pub fn inverse(x: &FlexData) -> FlexData {
+    unary(x, |v| 1.0 / v)
+}
+
+/// Round half away from zero at a number of decimal digits, negative digits
+/// rounding to tens, hundreds...
+pub fn round(x: &FlexData, digits: i32) -> FlexData {
+    at_digits(x, digits, f64::round)
+}
+
+/// Largest value not greater than x at a number of decimal digits
+pub fn floor(x: &FlexData, digits: i32) -> FlexData {
+    at_digits(x, digits, f64::floor)
+}
+
+/// Smallest value not lower than x at a number of decimal digits
+pub fn ceil(x: &FlexData, digits: i32) -> FlexData {
+    at_digits(x, digits, f64::ceil)
+}
+
+/// Limit x to [lower, upper], a missing bound leaving that side open
+pub fn clip(x: &FlexData, lower: Option<f64>, upper: Option<f64>) -> FlexData {
+    unary(x, |v| {
+        let v = lower.map_or(v, |lo| v.max(lo));
+        upper.map_or(v, |hi| v.min(hi))
+    })
+}
+
+pub fn sin(x: &FlexData) -> FlexData {
+    unary(x, f64::sin)
+}
+
+pub fn cos(x: &FlexData) -> FlexData {
+    unary(x, f64::cos)
+}
+
+pub fn tan(x: &FlexData) -> FlexData {
+    unary(x, f64::tan)
+}
+
+pub fn asin(x: &FlexData) -> FlexData {
+    unary(x, f64::asin)
+}
+
+pub fn acos(x: &FlexData) -> FlexData {
+    unary(x, f64::acos)
+}
+
+pub fn atan(x: &FlexData) -> FlexData {
+    unary(x, f64::atan)
+}
+
+/// Log-odds of a probability in (0,1)
+pub fn logit(x: &FlexData) -> FlexData {
+    unary(x, |p| (p / (1.0 - p)).ln())
+}
+
+/// Probability of log-odds, inverse of logit
+pub fn sigmoid(x: &FlexData) -> FlexData {
+    unary(x, |v| 1.0 / (1.0 + (-v).exp()))
+}
@@ -0,0 +1,50 @@
+/// Primitive numbers which can be converted to f64 for statistics
+pub trait Numeric: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_numeric {
+    ($($t:ty),*) => {
+        $(
+            impl Numeric for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_numeric!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+/// Division which is None where it is undefined, such as a division by zero
+pub trait CheckedDiv: Copy {
+    fn checked_div(self, divisor: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_div_int {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedDiv for $t {
+                fn checked_div(self, divisor: Self) -> Option<Self> {
+                    <$t>::checked_div(self, divisor)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_checked_div_float {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedDiv for $t {
+                fn checked_div(self, divisor: Self) -> Option<Self> {
+                    if divisor != 0.0 { Some( self / divisor ) } else { None }
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_div_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+impl_checked_div_float!(f32, f64);
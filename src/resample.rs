@@ -0,0 +1,172 @@
+use std::ops::{Add, Range};
+use crate::{DataPoint, Interval, Numeric, Series, Side, TimeIndex};
+
+/// Open, high, low and close values of a time bucket
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Ohlc<U> {
+    open: U,
+    high: U,
+    low: U,
+    close: U
+}
+
+impl<U> Ohlc<U> {
+
+    pub fn new(open: U, high: U, low: U, close: U) -> Self {
+        Self { open, high, low, close }
+    }
+
+    pub fn get_open(&self) -> &U {
+        &self.open
+    }
+
+    pub fn get_high(&self) -> &U {
+        &self.high
+    }
+
+    pub fn get_low(&self) -> &U {
+        &self.low
+    }
+
+    pub fn get_close(&self) -> &U {
+        &self.close
+    }
+}
+
+/// Points of a series grouped into consecutive time buckets. Buckets are closed
+/// on the left, [start, end), or on the right, (start, end], and are labelled
+/// by either of their bounds. Aggregations only output buckets holding points.
+pub struct Resampler<'a, T, U> {
+    source: &'a Series<T,U>,
+    interval: Interval,
+    label: Side,
+    buckets: Vec<(i64, Range<usize>)>
+}
+
+impl<'a, T: TimeIndex, U: PartialOrd + Clone> Resampler<'a, T, U> {
+
+    /// Rejects invalid intervals and buckets whose label is out of the range of T
+    pub(crate) fn new(source: &'a Series<T,U>, interval: Interval, closed: Side, label: Side) -> Result<Self, &'static str> {
+        interval.checked_millis()?;
+        let mut buckets : Vec<(i64, Range<usize>)> = Vec::new();
+        for (i,dp) in source.points().iter().enumerate() {
+            let millis = dp.get_index().to_millis();
+            // A timestamp is in (start, end] when the previous millisecond is in [start, end)
+            let start = match closed {
+                Side::Left => interval.bucket_start(millis),
+                Side::Right => interval.bucket_start(millis - 1)
+            };
+            match buckets.last_mut() {
+                Some( (last, range) ) if *last == start => range.end = i + 1,
+                _ => buckets.push( (start, i..i+1) )
+            }
+        }
+        let resampler = Self {
+            source,
+            interval,
+            label,
+            buckets
+        };
+        // Labels of the buckets in between lie within the first and last ones
+        let bounds = [resampler.buckets.first(), resampler.buckets.last()];
+        if bounds.iter().flatten().any(|(start,_)| resampler.try_label_of(*start).is_none()) {
+            return Err("Bucket label out of the range of the index");
+        }
+        Ok( resampler )
+    }
+
+    /// Apply a function to the points of each bucket
+    pub fn apply<V>(&self, f: impl Fn(&[DataPoint<T,U>]) -> V) -> Series<T,V> {
+        let data : Vec<DataPoint<T,V>> = self.buckets.iter()
+            .map(|(start, range)| DataPoint::new(self.label_of(*start), f(&self.source.points_in(range.clone()))))
+            .collect();
+        self.source.with_data(data)
+    }
+
+    pub fn count(&self) -> Series<T,usize> {
+        self.apply(|points| points.len())
+    }
+
+    pub fn first(&self) -> Series<T,U> {
+        self.apply(|points| points[0].get().clone())
+    }
+
+    pub fn last(&self) -> Series<T,U> {
+        self.apply(|points| points[points.len()-1].get().clone())
+    }
+
+    pub fn max(&self) -> Series<T,U> {
+        self.apply(|points| extremum(points, |x,y| x > y))
+    }
+
+    pub fn min(&self) -> Series<T,U> {
+        self.apply(|points| extremum(points, |x,y| x < y))
+    }
+
+    pub fn ohlc(&self) -> Series<T,Ohlc<U>> {
+        self.apply(|points| Ohlc::new(
+            points[0].get().clone(),
+            extremum(points, |x,y| x > y),
+            extremum(points, |x,y| x < y),
+            points[points.len()-1].get().clone()
+        ))
+    }
+
+    /// Upsample to every bucket between the first and last points, each bucket
+    /// holding its last value or else the last value of the buckets before it
+    pub fn ffill(&self) -> Series<T,U> {
+        let points = self.source.points();
+        let mut data : Vec<DataPoint<T,U>> = Vec::new();
+        if let (Some( (first, _) ), Some( (last, _) )) = (self.buckets.first(), self.buckets.last()) {
+            let mut buckets = self.buckets.iter().peekable();
+            let mut value = points[0].get().clone();
+            let mut start = *first;
+            while start <= *last {
+                if let Some( (_, range) ) = buckets.next_if(|(s,_)| *s == start) {
+                    value = points[range.end-1].get().clone();
+                }
+                data.push( DataPoint::new(self.label_of(start), value.clone()) );
+                start = self.interval.next_bucket(start);
+            }
+        }
+        self.source.with_data(data)
+    }
+
+    fn label_of(&self, start: i64) -> T {
+        self.try_label_of(start).expect("Bucket labels are checked on creation")
+    }
+
+    fn try_label_of(&self, start: i64) -> Option<T> {
+        match self.label {
+            Side::Left => T::from_millis(start),
+            Side::Right => T::from_millis( self.interval.next_bucket(start) )
+        }
+    }
+}
+
+impl<'a, T: TimeIndex, U: PartialOrd + Add<Output=U> + Copy> Resampler<'a, T, U> {
+
+    pub fn sum(&self) -> Series<T,U> {
+        self.apply(|points| points[1..].iter().fold(*points[0].get(), |acc,dp| acc + *dp.get()))
+    }
+
+}
+
+impl<'a, T: TimeIndex, U: Numeric> Resampler<'a, T, U> {
+
+    pub fn mean(&self) -> Series<T,f64> {
+        self.apply(|points| points.iter().map(|dp| dp.get().to_f64()).sum::<f64>() / points.len() as f64)
+    }
+
+}
+
+/// Value of the points preferred by better(x, current best)
+fn extremum<T,U: Clone>(points: &[DataPoint<T,U>], better: impl Fn(&U,&U) -> bool) -> U {
+    let mut best = points[0].get();
+    for dp in points[1..].iter() {
+        if better(dp.get(), best) {
+            best = dp.get();
+        }
+    }
+    best.clone()
+}
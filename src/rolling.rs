@@ -0,0 +1,248 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use crate::QuantileMethod;
+use crate::stats;
+
+/// Series on which windowed and exponentially weighted statistics can be computed
+pub trait Rollable {
+    type Output;
+    /// Values as f64, None standing for missing values
+    fn rolling_values(&self) -> Vec<Option<f64>>;
+    /// Values of other as f64 at the indices of the series, None standing for
+    /// missing values and indices not found in other
+    fn aligned_values(&self, other: &Self) -> Vec<Option<f64>>;
+    /// Series holding the computed values at the original indices
+    fn rolling_output(&self, values: Vec<Option<f64>>) -> Self::Output;
+}
+
+/// Moving window over a series. Each window is a range of positions and a
+/// statistic is only computed when the window holds at least min_periods
+/// values which are not missing.
+pub struct Rolling<'a, S> {
+    source: &'a S,
+    values: Vec<Option<f64>>,
+    bounds: Vec<(usize,usize)>,
+    min_periods: usize
+}
+
+impl<'a, S: Rollable> Rolling<'a, S> {
+
+    pub(crate) fn new(source: &'a S, bounds: Vec<(usize,usize)>, min_periods: usize) -> Self {
+        Self {
+            source,
+            values: source.rolling_values(),
+            bounds,
+            min_periods: min_periods.max(1)
+        }
+    }
+
+    pub fn count(&self) -> S::Output {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut counter = WindowCounter::default();
+        for &(start, end) in self.bounds.iter() {
+            counter.slide(&self.values, start, end);
+            res.push( Some( counter.n as f64 ) );
+        }
+        self.source.rolling_output(res)
+    }
+
+    pub fn sum(&self) -> S::Output {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut counter = WindowCounter::default();
+        for &(start, end) in self.bounds.iter() {
+            counter.slide(&self.values, start, end);
+            res.push( if counter.n >= self.min_periods { Some( counter.sum ) } else { None } );
+        }
+        self.source.rolling_output(res)
+    }
+
+    pub fn mean(&self) -> S::Output {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut counter = WindowCounter::default();
+        for &(start, end) in self.bounds.iter() {
+            counter.slide(&self.values, start, end);
+            res.push( if counter.n >= self.min_periods { Some( counter.mean ) } else { None } );
+        }
+        self.source.rolling_output(res)
+    }
+
+    pub fn var(&self, is_sample: bool) -> S::Output {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut counter = WindowCounter::default();
+        let ddof = if is_sample { 1 } else { 0 };
+        for &(start, end) in self.bounds.iter() {
+            counter.slide(&self.values, start, end);
+            res.push( if counter.n >= self.min_periods && counter.n > ddof {
+                Some( counter.m2.max(0.0) / (counter.n - ddof) as f64 )
+            } else {
+                None
+            });
+        }
+        self.source.rolling_output(res)
+    }
+
+    pub fn std(&self, is_sample: bool) -> S::Output {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut counter = WindowCounter::default();
+        let ddof = if is_sample { 1 } else { 0 };
+        for &(start, end) in self.bounds.iter() {
+            counter.slide(&self.values, start, end);
+            res.push( if counter.n >= self.min_periods && counter.n > ddof {
+                Some( (counter.m2.max(0.0) / (counter.n - ddof) as f64).sqrt() )
+            } else {
+                None
+            });
+        }
+        self.source.rolling_output(res)
+    }
+
+    pub fn min(&self) -> S::Output {
+        self.source.rolling_output( self.extremum(|a,b| a <= b) )
+    }
+
+    pub fn max(&self) -> S::Output {
+        self.source.rolling_output( self.extremum(|a,b| a >= b) )
+    }
+
+    pub fn median(&self) -> S::Output {
+        self.quantile(0.5, &QuantileMethod::Linear)
+    }
+
+    pub fn quantile(&self, q: f64, method: &QuantileMethod) -> S::Output {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut window : Vec<f64> = Vec::new();
+        let (mut lo, mut hi) = (0usize, 0usize);
+        for &(start, end) in self.bounds.iter() {
+            while hi < end {
+                if let Some( x ) = self.values[hi] {
+                    let pos = window.partition_point(|y| y.partial_cmp(&x) == Some(Ordering::Less));
+                    window.insert(pos, x);
+                }
+                hi += 1;
+            }
+            while lo < start {
+                if let Some( x ) = self.values[lo] {
+                    let pos = window.partition_point(|y| y.partial_cmp(&x) == Some(Ordering::Less));
+                    window.remove(pos);
+                }
+                lo += 1;
+            }
+            res.push( if window.len() >= self.min_periods { stats::quantile_sorted(&window, q, method) } else { None } );
+        }
+        self.source.rolling_output(res)
+    }
+
+    /// Apply a function to the values of each window which are not missing
+    pub fn apply(&self, f: impl Fn(&[f64]) -> f64) -> S::Output {
+        let res : Vec<Option<f64>> = self.bounds.iter()
+            .map(|&(start, end)| {
+                let window : Vec<f64> = self.values[start..end].iter().flatten().cloned().collect();
+                if window.len() >= self.min_periods { Some( f(&window) ) } else { None }
+            })
+            .collect();
+        self.source.rolling_output(res)
+    }
+
+    /// Monotonic deque of candidate positions, keep(a,b) telling whether a
+    /// remains a candidate when b enters the window
+    fn extremum(&self, keep: impl Fn(f64, f64) -> bool) -> Vec<Option<f64>> {
+        let mut res : Vec<Option<f64>> = Vec::new();
+        let mut candidates : VecDeque<usize> = VecDeque::new();
+        let mut counter = WindowCounter::default();
+        let mut hi = 0usize;
+        for &(start, end) in self.bounds.iter() {
+            while hi < end {
+                if let Some( x ) = self.values[hi] {
+                    while candidates.back().is_some_and(|&k| !keep(self.values[k].unwrap(), x)) {
+                        candidates.pop_back();
+                    }
+                    candidates.push_back(hi);
+                }
+                hi += 1;
+            }
+            while candidates.front().is_some_and(|&k| k < start) {
+                candidates.pop_front();
+            }
+            counter.slide(&self.values, start, end);
+            res.push( if counter.n >= self.min_periods {
+                candidates.front().and_then(|&k| self.values[k])
+            } else {
+                None
+            });
+        }
+        res
+    }
+}
+
+/// Running count, sum, mean and sum of squared deviations of a sliding window
+#[derive(Default)]
+struct WindowCounter {
+    lo: usize,
+    hi: usize,
+    n: usize,
+    sum: f64,
+    mean: f64,
+    m2: f64
+}
+
+impl WindowCounter {
+
+    fn slide(&mut self, values: &[Option<f64>], start: usize, end: usize) {
+        while self.hi < end {
+            if let Some( x ) = values[self.hi] {
+                self.n += 1;
+                self.sum += x;
+                let dx = x - self.mean;
+                self.mean += dx / self.n as f64;
+                self.m2 += dx * (x - self.mean);
+            }
+            self.hi += 1;
+        }
+        while self.lo < start {
+            if let Some( x ) = values[self.lo] {
+                self.n -= 1;
+                self.sum -= x;
+                if self.n == 0 {
+                    self.sum = 0.0;
+                    self.mean = 0.0;
+                    self.m2 = 0.0;
+                } else {
+                    let dx = x - self.mean;
+                    self.mean -= dx / self.n as f64;
+                    self.m2 -= dx * (x - self.mean);
+                }
+            }
+            self.lo += 1;
+        }
+    }
+}
+
+/// Window bounds covering a fixed number of positions
+pub(crate) fn count_bounds(size: usize, window: usize, center: bool) -> Vec<(usize,usize)> {
+    (0..size)
+        .map(|i| {
+            let last = if center { i + (window - 1) / 2 } else { i };
+            let start = (last + 1).saturating_sub(window);
+            (start, (last + 1).min(size))
+        })
+        .collect()
+}
+
+/// Window bounds covering a time span, given sorted timestamps in milliseconds.
+/// Trailing windows cover (t - span, t] and centered ones (t - span/2, t + span/2].
+pub(crate) fn span_bounds(timestamps: &[i64], span: i64, center: bool) -> Vec<(usize,usize)> {
+    let (before, after) = if center { (span / 2, span - span / 2) } else { (span, 0) };
+    let mut start = 0usize;
+    let mut end = 0usize;
+    timestamps.iter()
+        .map(|&t| {
+            while start < timestamps.len() && timestamps[start] <= t - before {
+                start += 1;
+            }
+            while end < timestamps.len() && timestamps[end] <= t + after {
+                end += 1;
+            }
+            (start, end)
+        })
+        .collect()
+}
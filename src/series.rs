@@ -1,13 +1,26 @@
-use crate::DataPoint;
+use crate::{Alignment, AsofDirection, CheckedDiv, DataPoint, Decay, Ewm, Interval, Numeric, Resampler, Rollable, Rolling, Side, TimeIndex};
+use crate::rolling::{count_bounds, span_bounds};
+use crate::helper::shift_position;
+use std::cmp::Ordering;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::collections::vec_deque;
 use std::ops::*;
 use std::convert::From;
+use std::fmt::Display;
+use std::io::Write;
+use std::str::FromStr;
+use rayon::prelude::*;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
 
+/// Series of points sorted by index. Bounded series evict their oldest point
+/// from the front of a ring buffer.
 #[derive(Debug, Clone)]
 pub struct Series<T,U> {
     id: String,
-    data: Vec<DataPoint<T,U>>,
-    opt_max_size: Option<usize>,
-    counter: usize
+    data: VecDeque<DataPoint<T,U>>,
+    opt_max_size: Option<usize>
 }
 
 impl<T: Ord + Clone,U: PartialOrd + Clone> Series<T,U> {
@@ -15,17 +28,24 @@ impl<T: Ord + Clone,U: PartialOrd + Clone> Series<T,U> {
     pub fn new(id: &str, opt_max_size: Option<usize>) -> Series<T,U> {
         Series{
             id: id.to_string(),
-            data: Vec::<DataPoint<T,U>>::new(),
-            opt_max_size,
-            counter: 0
+            data: VecDeque::<DataPoint<T,U>>::new(),
+            opt_max_size
         }
     }
 
-    pub fn from_vec(id: &str, items: Vec<DataPoint<T,U>>) -> Series<T,U> {
-        let mut ts = Series::new( id, None );
+    /// Create a Series from items in any order, sorted once. Items sharing an
+    /// index are replaced by the last of them.
+    pub fn from_vec(id: &str, mut items: Vec<DataPoint<T,U>>) -> Series<T,U> {
+        items.sort_by(|a,b| a.get_index().cmp(b.get_index()));
+        let mut data : Vec<DataPoint<T,U>> = Vec::with_capacity(items.len());
         for item in items {
-            ts.insert_update( item );
+            match data.last_mut() {
+                Some( last ) if last.get_index() == item.get_index() => *last = item,
+                _ => data.push( item )
+            }
         }
+        let mut ts = Series::new( id, None );
+        ts.data = data.into();
         ts
     }
 
@@ -33,84 +53,346 @@ impl<T: Ord + Clone,U: PartialOrd + Clone> Series<T,U> {
         self.id.as_str()
     }
 
-    /// Insert item or update if it already exists
-    pub fn insert_update(&mut self, item: DataPoint<T,U>) {
-        if let Some(k) = self.data.iter().position(|x| x.get_index() == item.get_index() ) {
-            self.data[k] = item;
-        } else {
-            self.data.push(item);
-            self.data.sort();
-            if let Some( maxsize ) = self.opt_max_size {
-                if self.data.len() > maxsize {
-                    self.data.remove(0);
-                }
+    pub fn get_size(&self) -> usize {
+        self.points().len()
+    }
+
+    pub fn get_max_size(&self) -> Option<usize> {
+        self.opt_max_size
+    }
+
+    /// Insert item or update if it already exists. Returns the point evicted
+    /// when a bounded series exceeds its maximum size.
+    pub fn insert_update(&mut self, item: DataPoint<T,U>) -> Option<DataPoint<T,U>> {
+        match self.search_insert( item.get_index() ) {
+            Ok( k ) => {
+                self.data[k] = item;
+                None
+            },
+            Err( k ) => self.insert_at(k, item)
+        }
+    }
+
+    /// Insert or update items, passing the evicted points to on_evict
+    pub fn extend(&mut self, items: impl IntoIterator<Item=DataPoint<T,U>>, mut on_evict: impl FnMut(DataPoint<T,U>)) {
+        for item in items {
+            if let Some( evicted ) = self.insert_update( item ) {
+                on_evict( evicted );
             }
         }
     }
 
     /// Get item corresponding to timesignature
     pub fn at(&self, index: &T, offset: i32) -> Option<DataPoint<T,U>> {
-        let optpos = self.data.iter().rposition(|x| x.get_index() == index);
-        match optpos {
-            Some( pos ) => {
-                if offset > 0{
-                    let newpos = pos + (offset as usize);
-                    if newpos < self.data.len() {
-                        Some( self.data[newpos].clone() )
-                    } else {
-                        None
-                    }
-                } else if pos >= (-offset as usize) {
-                        let newpos = pos - (-offset as usize);
-                        Some( self.data[newpos].clone() )
-                } else {
-                    None
-                }
-            },
-            None => None
-        }
+        let pos = self.search( index ).ok()?;
+        self.offset_position(pos, offset as i64)
+            .map(|k| self.points()[k].clone())
+    }
+
+    /// Get the last item whose index is at or before index
+    pub fn at_or_before(&self, index: &T) -> Option<DataPoint<T,U>> {
+        let pos = self.points().partition_point(|x| x.get_index() <= index);
+        if pos > 0 { Some( self.points()[pos-1].clone() ) } else { None }
+    }
+
+    /// Get the first item whose index is at or after index
+    pub fn at_or_after(&self, index: &T) -> Option<DataPoint<T,U>> {
+        let pos = self.points().partition_point(|x| x.get_index() < index);
+        self.points().get(pos).cloned()
     }
 
     /// Get range with reference timesignature and startoffset
     pub fn range_at(&self, index: &T, size: usize, offset: i32) -> Vec<DataPoint<T,U>> {
-        let mut res : Vec<DataPoint<T,U>> = Vec::new();
-        for i in 0..size {
-            if let Some( data ) = self.at(index, offset + (i as i32)) {
-                res.push( data );
-            }
+        match self.search( index ) {
+            Ok( pos ) => (0..size)
+                .filter_map(|i| self.offset_position(pos, offset as i64 + i as i64))
+                .map(|k| self.points()[k].clone())
+                .collect(),
+            Err( _ ) => Vec::new()
         }
-        res
     }
 
-    /// Get range from start to end inclusive
+    /// Get range from start to end inclusive. Negative positions count from
+    /// the end and out of bounds positions are clamped.
     pub fn range(&self, start: i32, end: i32) -> Vec<DataPoint<T,U>> {
-        let is : usize = if start >= 0 { start as usize } else { self.data.len() - start.unsigned_abs() as usize };
-        let ie : usize = if end >= 0 { end as usize } else { self.data.len() - end.unsigned_abs() as usize };
-        let mut res : Vec<DataPoint<T,U>> = Vec::new();
-        for i in is..=ie {
-            res.push( self[i].clone() )
+        let n = self.get_size() as i64;
+        let resolve = |pos: i32| -> i64 { if pos >= 0 { pos as i64 } else { n + pos as i64 } };
+        let is = resolve(start).max(0);
+        let ie = resolve(end).min(n - 1);
+        if is <= ie {
+            self.points().range(is as usize..=ie as usize).cloned().collect()
+        } else {
+            Vec::new()
         }
-        res
     }
 
-}
+    /// Get the items whose index lies within the range, e.g. `start..end` or `start..`
+    pub fn slice<R: RangeBounds<T>>(&self, range: R) -> vec_deque::Iter<'_, DataPoint<T,U>> {
+        let points = self.points();
+        let is = match range.start_bound() {
+            Bound::Included( start ) => points.partition_point(|x| x.get_index() < start),
+            Bound::Excluded( start ) => points.partition_point(|x| x.get_index() <= start),
+            Bound::Unbounded => 0
+        };
+        let ie = match range.end_bound() {
+            Bound::Included( end ) => points.partition_point(|x| x.get_index() <= end),
+            Bound::Excluded( end ) => points.partition_point(|x| x.get_index() < end),
+            Bound::Unbounded => points.len()
+        };
+        points.range(is..ie.max(is))
+    }
 
-impl<T: Ord + Clone,U: PartialOrd + Add<Output=U> + Copy> Series<T,U> {
+    /// Get the items whose index lies between start and end
+    pub fn between(&self, start: &T, end: &T, include_start: bool, include_end: bool) -> vec_deque::Iter<'_, DataPoint<T,U>> {
+        let lower = if include_start { Bound::Included( start ) } else { Bound::Excluded( start ) };
+        let upper = if include_end { Bound::Included( end ) } else { Bound::Excluded( end ) };
+        self.slice( (lower, upper) )
+    }
 
-    /// Insert item or add data if it exists
-    pub fn insert_add(&mut self, item: DataPoint<T,U>) {
-        if let Some(k) = self.data.iter().position(|x| x.get_index() == item.get_index() ) {
-            let val = self.data[k].get().to_owned();
-            self.data[k].set( val + *item.get() );
-        } else {
-            self.data.push(item);
-            self.data.sort();
-            if let Some( maxsize ) = self.opt_max_size {
-                if self.data.len() > maxsize {
-                    self.data.remove(0);
+    /// Get the items whose index is at or after start
+    pub fn since(&self, start: &T) -> vec_deque::Iter<'_, DataPoint<T,U>> {
+        self.slice( (Bound::Included( start ), Bound::Unbounded) )
+    }
+
+    /// Get the items whose index is at or before end
+    pub fn until(&self, end: &T) -> vec_deque::Iter<'_, DataPoint<T,U>> {
+        self.slice( (Bound::Unbounded, Bound::Included( end )) )
+    }
+
+    /// Create the Series of its running maximum
+    pub fn cummax(&self) -> Series<T,U> {
+        self.accumulate(|acc,x| if x > acc { x.clone() } else { acc.clone() })
+    }
+
+    /// Create the Series of its running minimum
+    pub fn cummin(&self) -> Series<T,U> {
+        self.accumulate(|acc,x| if x < acc { x.clone() } else { acc.clone() })
+    }
+
+    /// Create the Series of the number of points up to each point
+    pub fn cumcount(&self) -> Series<T,usize> {
+        let data : Vec<DataPoint<T,usize>> = self.points().iter()
+            .enumerate()
+            .map(|(i,dp)| DataPoint::new(dp.get_index().clone(), i + 1))
+            .collect();
+        Series { opt_max_size: self.opt_max_size, ..self.with_data(data) }
+    }
+
+    /// Lag values by n points, or lead them when n is negative. Points without
+    /// a source value get fill.
+    pub fn shift(&self, n: i32, fill: U) -> Series<T,U> {
+        let data : Vec<DataPoint<T,U>> = self.points().iter()
+            .enumerate()
+            .map(|(i,dp)| {
+                let val = shift_position(i, n, self.get_size()).map_or(fill.clone(), |j| self.points()[j].get().clone());
+                DataPoint::new(dp.get_index().clone(), val)
+            })
+            .collect();
+        self.with_data(data)
+    }
+
+    fn accumulate(&self, f: impl Fn(&U,&U) -> U) -> Series<T,U> {
+        let mut data : Vec<DataPoint<T,U>> = Vec::new();
+        for dp in self.points().iter() {
+            let val = match data.last() {
+                Some( prev ) => f(prev.get(), dp.get()),
+                None => dp.get().clone()
+            };
+            data.push( DataPoint::new(dp.get_index().clone(), val) );
+        }
+        Series { opt_max_size: self.opt_max_size, ..self.with_data(data) }
+    }
+
+    /// Create the Series of the values mapped by f
+    pub fn map<V>(&self, f: impl Fn(&U) -> V) -> Series<T,V> {
+        let data : Vec<DataPoint<T,V>> = self.points().iter()
+            .map(|dp| DataPoint::new(dp.get_index().clone(), f(dp.get())))
+            .collect();
+        self.with_data(data)
+    }
+
+    /// Combine the values of both series over the aligned indices, the value
+    /// missing from either series being None. Points where f returns None are skipped.
+    pub fn zip_with<V,W>(&self, other: &Series<T,V>, alignment: &Alignment, f: impl Fn(Option<&U>, Option<&V>) -> Option<W>) -> Series<T,W> {
+        let (left, right) = (self.points(), other.points());
+        let (mut i, mut j) = (0usize, 0usize);
+        let mut data : Vec<DataPoint<T,W>> = Vec::new();
+        while i < left.len() || j < right.len() {
+            let order = match (left.get(i), right.get(j)) {
+                (Some( x ), Some( y )) => x.get_index().cmp(y.get_index()),
+                (Some( _ ), None) => Ordering::Less,
+                _ => Ordering::Greater
+            };
+            let (index, x, y) = match order {
+                Ordering::Less => {
+                    i += 1;
+                    (left[i-1].get_index(), Some( left[i-1].get() ), None)
+                },
+                Ordering::Greater => {
+                    j += 1;
+                    (right[j-1].get_index(), None, Some( right[j-1].get() ))
+                },
+                Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                    (left[i-1].get_index(), Some( left[i-1].get() ), Some( right[j-1].get() ))
+                }
+            };
+            let aligned = match alignment {
+                Alignment::Inner => x.is_some() && y.is_some(),
+                Alignment::Left => x.is_some(),
+                Alignment::Outer => true
+            };
+            if aligned {
+                if let Some( w ) = f(x, y) {
+                    data.push( DataPoint::new(index.clone(), w) );
                 }
             }
         }
+        self.with_data(data)
+    }
+
+    /// Binary search of the position of index
+    fn search(&self, index: &T) -> Result<usize, usize> {
+        self.points().binary_search_by(|x| x.get_index().cmp(index))
+    }
+
+    /// Same as search, without searching when index comes after the last item
+    fn search_insert(&self, index: &T) -> Result<usize, usize> {
+        match self.points().back() {
+            Some( last ) if last.get_index() < index => Err( self.get_size() ),
+            None => Err( 0 ),
+            _ => self.search( index )
+        }
+    }
+
+    fn offset_position(&self, pos: usize, offset: i64) -> Option<usize> {
+        let target = pos as i64 + offset;
+        if target >= 0 && (target as usize) < self.get_size() { Some( target as usize ) } else { None }
+    }
+
+    /// Insert item at position k, evicting the oldest item beyond the maximum size
+    fn insert_at(&mut self, k: usize, item: DataPoint<T,U>) -> Option<DataPoint<T,U>> {
+        self.data.insert(k, item);
+        match self.opt_max_size {
+            Some( maxsize ) if self.data.len() > maxsize => self.data.pop_front(),
+            _ => None
+        }
+    }
+
+    /// Unbounded Series with the same id
+    pub(crate) fn with_data<V>(&self, data: Vec<DataPoint<T,V>>) -> Series<T,V> {
+        Series {
+            id: self.id.clone(),
+            data: data.into(),
+            opt_max_size: None
+        }
+    }
+
+}
+
+impl<T: Ord + Clone,U: PartialOrd + Clone + Display> Series<T,U> {
+
+    /// Write the points to a CSV file with an index and a value column
+    pub fn to_csv(&self, filepath: &str, index_label: &str, value_label: &str, format_index: impl Fn(&T) -> String) -> Result<(), &'static str> {
+        let mut text = format!("{},{}\n", index_label, value_label);
+        for dp in self.points() {
+            text.push_str( format!("{},{}\n", format_index(dp.get_index()), dp.get()).as_str() );
+        }
+        let mut file = std::fs::File::create(filepath).map_err(|_| "File creation failed")?;
+        file.write_all( text.as_bytes() ).map_err(|_| "Writing failed")
+    }
+
+}
+
+impl<T: Ord + Clone,U: PartialOrd + Clone + FromStr> Series<T,U> {
+
+    /// Create a Series from CSV text, taking the indices parsed by parse_index and
+    /// the values from the columns with the given labels
+    pub fn from_csv(id: &str, text: &str, index_label: &str, value_label: &str, parse_index: impl Fn(&str) -> Option<T>) -> Result<Series<T,U>, &'static str> {
+        let mut filtered_text = text.to_string();
+        filtered_text.retain(|c| c != '"');
+        let mut lines = filtered_text.lines();
+        let headers : Vec<&str> = lines.next().ok_or("Missing CSV headers")?.split(',').collect();
+        let index_pos = headers.iter().position(|&h| h == index_label).ok_or("Label not found")?;
+        let value_pos = headers.iter().position(|&h| h == value_label).ok_or("Label not found")?;
+        let mut items : Vec<DataPoint<T,U>> = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let tokens : Vec<&str> = line.split(',').collect();
+            let index = tokens.get(index_pos).and_then(|token| parse_index(token)).ok_or("Index can not be parsed")?;
+            let value = tokens.get(value_pos).and_then(|token| token.parse::<U>().ok()).ok_or("Value can not be parsed")?;
+            items.push( DataPoint::new(index, value) );
+        }
+        Ok( Series::from_vec(id, items) )
+    }
+
+}
+
+impl<T: Ord + Clone + Serialize,U: PartialOrd + Clone + Serialize> Series<T,U> {
+
+    pub fn to_json(&self) -> Result<String, &'static str> {
+        serde_json::to_string(self).map_err(|_| "Serialization failed")
+    }
+
+}
+
+impl<T: Ord + Clone + DeserializeOwned,U: PartialOrd + Clone + DeserializeOwned> Series<T,U> {
+
+    pub fn from_json(text: &str) -> Result<Series<T,U>, &'static str> {
+        serde_json::from_str(text).map_err(|_| "Deserialization failed")
+    }
+
+}
+
+/// Serialized form of a Series, holding its live points only
+#[derive(Serialize)]
+struct SeriesRef<'a,T,U> {
+    id: &'a str,
+    opt_max_size: Option<usize>,
+    data: &'a VecDeque<DataPoint<T,U>>
+}
+
+#[derive(Deserialize)]
+struct SeriesData<T,U> {
+    id: String,
+    opt_max_size: Option<usize>,
+    data: Vec<DataPoint<T,U>>
+}
+
+impl<T: Serialize,U: Serialize> Serialize for Series<T,U> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SeriesRef { id: self.id.as_str(), opt_max_size: self.opt_max_size, data: self.points() }.serialize(serializer)
+    }
+}
+
+/// Points are sorted once and only the newest ones are kept within the maximum size
+impl<'de,T: Ord + Clone + Deserialize<'de>,U: PartialOrd + Clone + Deserialize<'de>> Deserialize<'de> for Series<T,U> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SeriesData { id, opt_max_size, data } = SeriesData::deserialize(deserializer)?;
+        let mut ts = Series::from_vec(id.as_str(), data);
+        if let Some( maxsize ) = opt_max_size {
+            let excess = ts.data.len().saturating_sub(maxsize);
+            ts.data.drain(..excess);
+        }
+        ts.opt_max_size = opt_max_size;
+        Ok(ts)
+    }
+}
+
+impl<T: Ord + Clone,U: PartialOrd + Add<Output=U> + Copy> Series<T,U> {
+
+    /// Insert item or add data if it exists. Returns the point evicted when a
+    /// bounded series exceeds its maximum size.
+    pub fn insert_add(&mut self, item: DataPoint<T,U>) -> Option<DataPoint<T,U>> {
+        match self.search_insert( item.get_index() ) {
+            Ok( k ) => {
+                let dp = &mut self.data[k];
+                let val = dp.get().to_owned();
+                dp.set( val + *item.get() );
+                None
+            },
+            Err( k ) => self.insert_at(k, item)
+        }
     }
 
 }
@@ -121,7 +403,7 @@ impl<T: Ord + Clone,U: PartialOrd + AddAssign + From<u8> + Copy> Series<T,U> {
     pub fn cumsum(&self) -> Series<T,U> {
         let mut running_total : U = 0.into();
         let mut ts = Series::<T,U>::new( self.id.as_str(), self.opt_max_size );
-        for mut dp in self.clone() {
+        for mut dp in self.iter().cloned() {
             running_total += *dp.get();
             dp.set( running_total );
             ts.insert_update( dp );
@@ -131,14 +413,329 @@ impl<T: Ord + Clone,U: PartialOrd + AddAssign + From<u8> + Copy> Series<T,U> {
 
 }
 
+impl<T: Ord + Clone,U: PartialOrd + MulAssign + From<u8> + Copy> Series<T,U> {
+
+    /// Create the Series of its cumulated product
+    pub fn cumprod(&self) -> Series<T,U> {
+        let mut running_product : U = 1.into();
+        let data : Vec<DataPoint<T,U>> = self.points().iter()
+            .map(|dp| {
+                running_product *= *dp.get();
+                DataPoint::new(dp.get_index().clone(), running_product)
+            })
+            .collect();
+        Series { opt_max_size: self.opt_max_size, ..self.with_data(data) }
+    }
+
+}
+
+impl<T: Ord + Clone,U: Numeric> Series<T,U> {
+
+    /// Create the Series of differences with the value n points before, or after
+    /// when n is negative. Points without such a value are NaN.
+    pub fn diff(&self, n: i32) -> Series<T,f64> {
+        self.with_lagged(n, |x,y| x - y)
+    }
+
+    /// Create the Series of relative changes from the value n points before, or
+    /// after when n is negative. Points without such a value or with a zero
+    /// base are NaN.
+    pub fn pct_change(&self, n: i32) -> Series<T,f64> {
+        self.with_lagged(n, |x,y| if y != 0.0 { x / y - 1.0 } else { f64::NAN })
+    }
+
+    /// Series of f(value, lagged value), NaN where there is no lagged value
+    fn with_lagged(&self, n: i32, f: impl Fn(f64,f64) -> f64) -> Series<T,f64> {
+        let points = self.points();
+        let data : Vec<DataPoint<T,f64>> = (0..points.len())
+            .map(|i| {
+                let value = shift_position(i, n, points.len())
+                    .map_or(f64::NAN, |j| f(points[i].get().to_f64(), points[j].get().to_f64()));
+                DataPoint::new(points[i].get_index().clone(), value)
+            })
+            .collect();
+        self.with_data(data)
+    }
+
+    /// Moving window over the last `window` points, or centered on each point.
+    /// Fails if window is 0.
+    pub fn rolling(&self, window: usize, min_periods: usize, center: bool) -> Result<Rolling<'_, Self>, &'static str> {
+        if window == 0 {
+            return Err("Rolling window must hold at least one value");
+        }
+        Ok( Rolling::new(self, count_bounds(self.get_size(), window, center), min_periods) )
+    }
+
+    /// Exponentially weighted statistics with a constant decay per point
+    pub fn ewm(&self, decay: Decay, adjust: bool, ignore_na: bool, min_periods: usize) -> Result<Ewm<'_, Self>, &'static str> {
+        let alpha = decay.alpha()?;
+        let size = self.get_size();
+        Ok( Ewm::new(self, vec![1.0 - alpha; size], vec![alpha; size], adjust, ignore_na, min_periods) )
+    }
+
+}
+
+impl<T: TimeIndex,U: Numeric> Series<T,U> {
+
+    /// Moving window over the points less than `span` older than each point,
+    /// or centered on each point
+    pub fn rolling_span(&self, span: Interval, min_periods: usize, center: bool) -> Result<Rolling<'_, Self>, &'static str> {
+        let span = span.checked_millis()?;
+        let timestamps : Vec<i64> = self.points().iter().map(|dp| dp.get_index().to_millis()).collect();
+        Ok( Rolling::new(self, span_bounds(&timestamps, span, center), min_periods) )
+    }
+
+    /// Exponentially weighted statistics where weights halve every `half_life`
+    /// of time elapsed between points
+    pub fn ewm_time(&self, half_life: Interval, adjust: bool, ignore_na: bool, min_periods: usize) -> Result<Ewm<'_, Self>, &'static str> {
+        let half_life = half_life.checked_millis()? as f64;
+        let mut factors = vec![1.0];
+        for (prev, next) in self.points().iter().zip(self.points().iter().skip(1)) {
+            let elapsed = next.get_index().to_millis() - prev.get_index().to_millis();
+            factors.push( 0.5f64.powf( elapsed as f64 / half_life ) );
+        }
+        factors.truncate( self.get_size() );
+        let alphas = factors.iter().map(|f| 1.0 - f).collect();
+        Ok( Ewm::new(self, factors, alphas, adjust, ignore_na, min_periods) )
+    }
+
+}
+
+impl<T: TimeIndex,U: PartialOrd + Clone> Series<T,U> {
+
+    /// Group points into consecutive time buckets of the interval, closed and
+    /// labelled on either side
+    pub fn resample(&self, interval: Interval, closed: Side, label: Side) -> Result<Resampler<'_,T,U>, &'static str> {
+        Resampler::new(self, interval, closed, label)
+    }
+
+    /// Get the item closest to index, the earlier one on ties, within tolerance if any
+    pub fn nearest(&self, index: &T, tolerance: Option<Interval>) -> Option<DataPoint<T,U>> {
+        self.asof_position(index, &AsofDirection::Nearest, tolerance)
+            .map(|pos| self.points()[pos].clone())
+    }
+
+    /// Pair each point with the value of other at the closest index in the given
+    /// direction, within tolerance if any
+    pub fn merge_asof<V: PartialOrd + Clone>(&self, other: &Series<T,V>, direction: &AsofDirection, tolerance: Option<Interval>) -> Series<T,(U,Option<V>)> {
+        let data : Vec<DataPoint<T,(U,Option<V>)>> = self.points().iter()
+            .map(|dp| {
+                let matched = other.asof_position(dp.get_index(), direction, tolerance)
+                    .map(|pos| other.points()[pos].get().clone());
+                DataPoint::new(dp.get_index().clone(), (dp.get().clone(), matched))
+            })
+            .collect();
+        self.with_data(data)
+    }
+
+    #[allow(clippy::unnecessary_map_or)]
+    fn asof_position(&self, index: &T, direction: &AsofDirection, tolerance: Option<Interval>) -> Option<usize> {
+        let millis = index.to_millis();
+        let points = self.points();
+        let distance = |pos: usize| (points[pos].get_index().to_millis() - millis).abs();
+        let after = points.partition_point(|x| x.get_index() < index);
+        let before = if after < points.len() && points[after].get_index() == index {
+            Some( after )
+        } else {
+            after.checked_sub(1)
+        };
+        let after = Some( after ).filter(|&pos| pos < points.len());
+        let pos = match direction {
+            AsofDirection::Backward => before,
+            AsofDirection::Forward => after,
+            AsofDirection::Nearest => match (before, after) {
+                (Some( b ), Some( a )) => if distance(a) < distance(b) { Some( a ) } else { Some( b ) },
+                (b, a) => b.or(a)
+            }
+        };
+        pos.filter(|&pos| tolerance.map_or(true, |tol| distance(pos) <= tol.as_millis()))
+    }
+
+}
+
+macro_rules! impl_series_op {
+    ($trait:ident, $method:ident, $aligned:ident, $doc:expr) => {
+        impl<T: Ord + Clone,U: PartialOrd + $trait<Output=U> + Copy> Series<T,U> {
+
+            #[doc = $doc]
+            /// over the aligned indices. A value missing from either series is
+            /// replaced by fill, or the index is skipped if there is none.
+            pub fn $aligned(&self, other: &Series<T,U>, alignment: &Alignment, fill: Option<U>) -> Series<T,U> {
+                self.zip_with(other, alignment, |x,y| {
+                    match (x.copied().or(fill), y.copied().or(fill)) {
+                        (Some( x ), Some( y )) => Some( x.$method(y) ),
+                        _ => None
+                    }
+                })
+            }
+
+        }
+
+        /// Element-wise operation over the indices found in both series
+        impl<T: Ord + Clone,U: PartialOrd + $trait<Output=U> + Copy> $trait<&Series<T,U>> for &Series<T,U> {
+            type Output = Series<T,U>;
+            fn $method(self, other: &Series<T,U>) -> Series<T,U> {
+                self.$aligned(other, &Alignment::Inner, None)
+            }
+        }
+
+        /// Operation between each value and a scalar
+        impl<T: Ord + Clone,U: PartialOrd + $trait<Output=U> + Copy> $trait<U> for &Series<T,U> {
+            type Output = Series<T,U>;
+            fn $method(self, scalar: U) -> Series<T,U> {
+                self.map(|x| x.$method(scalar))
+            }
+        }
+    };
+}
+
+impl_series_op!(Add, add, add_aligned, "Element-wise sum");
+impl_series_op!(Sub, sub, sub_aligned, "Element-wise difference");
+impl_series_op!(Mul, mul, mul_aligned, "Element-wise product");
+
+impl<T: Ord + Clone,U: PartialOrd + CheckedDiv> Series<T,U> {
+
+    /// Element-wise quotient over the aligned indices. A value missing from
+    /// either series is replaced by fill, or the index is skipped if there is
+    /// none. Indices where the quotient is undefined, such as a division by
+    /// zero, are skipped.
+    pub fn div_aligned(&self, other: &Series<T,U>, alignment: &Alignment, fill: Option<U>) -> Series<T,U> {
+        self.zip_with(other, alignment, |x,y| {
+            match (x.copied().or(fill), y.copied().or(fill)) {
+                (Some( x ), Some( y )) => x.checked_div(y),
+                _ => None
+            }
+        })
+    }
+
+}
+
+/// Element-wise quotient over the indices found in both series, skipping
+/// divisions by zero
+impl<T: Ord + Clone,U: PartialOrd + CheckedDiv> Div<&Series<T,U>> for &Series<T,U> {
+    type Output = Series<T,U>;
+    fn div(self, other: &Series<T,U>) -> Series<T,U> {
+        self.div_aligned(other, &Alignment::Inner, None)
+    }
+}
+
+/// Quotient of each value by a scalar. Points whose quotient is undefined are
+/// skipped, so dividing by a zero scalar gives an empty series rather than
+/// panicking.
+impl<T: Ord + Clone,U: PartialOrd + CheckedDiv> Div<U> for &Series<T,U> {
+    type Output = Series<T,U>;
+    fn div(self, scalar: U) -> Series<T,U> {
+        let data : Vec<DataPoint<T,U>> = self.points().iter()
+            .filter_map(|dp| Some( DataPoint::new(dp.get_index().clone(), dp.get().checked_div(scalar)?) ))
+            .collect();
+        self.with_data(data)
+    }
+}
+
+/// Missing values are represented by f64::NAN
+impl<T: Ord + Clone,U: Numeric> Rollable for Series<T,U> {
+    type Output = Series<T,f64>;
+
+    fn rolling_values(&self) -> Vec<Option<f64>> {
+        self.points().iter()
+            .map(|dp| Some( dp.get().to_f64() ).filter(|x| !x.is_nan()))
+            .collect()
+    }
+
+    fn aligned_values(&self, other: &Self) -> Vec<Option<f64>> {
+        let mut j = 0usize;
+        self.points().iter()
+            .map(|dp| {
+                while j < other.points().len() && other.points()[j].get_index() < dp.get_index() {
+                    j += 1;
+                }
+                other.points().get(j)
+                    .filter(|odp| odp.get_index() == dp.get_index())
+                    .map(|odp| odp.get().to_f64())
+                    .filter(|x| !x.is_nan())
+            })
+            .collect()
+    }
+
+    fn rolling_output(&self, values: Vec<Option<f64>>) -> Series<T,f64> {
+        let data : Vec<DataPoint<T,f64>> = self.points().iter()
+            .zip(values)
+            .map(|(dp,x)| DataPoint::new(dp.get_index().clone(), x.unwrap_or(f64::NAN)))
+            .collect();
+        self.with_data(data)
+    }
+}
+
+impl<T,U> Series<T,U> {
+
+    /// Points in index order
+    pub(crate) fn points(&self) -> &VecDeque<DataPoint<T,U>> {
+        &self.data
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, DataPoint<T,U>> {
+        self.points().iter()
+    }
+
+    /// Iterate over the indices and mutable values
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=(&T, &mut U)> {
+        self.data.iter_mut().map(|dp| dp.parts_mut())
+    }
+
+}
+
+impl<T: Clone,U: Clone> Series<T,U> {
+
+    /// Points at the positions in range, borrowed unless they straddle the
+    /// wrap of the ring buffer
+    pub(crate) fn points_in(&self, range: Range<usize>) -> Cow<'_, [DataPoint<T,U>]> {
+        let (head, tail) = self.data.as_slices();
+        let split = head.len();
+        if range.end <= split {
+            Cow::Borrowed( &head[range] )
+        } else if range.start >= split {
+            Cow::Borrowed( &tail[range.start - split..range.end - split] )
+        } else {
+            Cow::Owned( self.data.range(range).cloned().collect() )
+        }
+    }
+
+}
+
+impl<T: Sync,U: Sync> Series<T,U> {
+
+    pub fn par_iter(&self) -> rayon::collections::vec_deque::Iter<'_, DataPoint<T,U>> {
+        self.points().par_iter()
+    }
+
+}
+
+impl<T,U> IntoIterator for Series<T,U> {
+    type Item = DataPoint<T,U>;
+    type IntoIter = std::collections::vec_deque::IntoIter<DataPoint<T,U>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a,T,U> IntoIterator for &'a Series<T,U> {
+    type Item = &'a DataPoint<T,U>;
+    type IntoIter = vec_deque::Iter<'a, DataPoint<T,U>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 // Implement [] operator
 impl<T,U> Index<i32> for Series<T,U> {
     type Output = DataPoint<T,U>;
     fn index(&self, index: i32) -> &DataPoint<T,U> {
         if index >= 0 {
-            &self.data[index as usize]
+            &self.points()[index as usize]
         } else {
-            &self.data[self.data.len() - (-index as usize)]
+            &self.points()[self.points().len() - (-index as usize)]
         }
     }
 }
@@ -146,20 +743,6 @@ impl<T,U> Index<i32> for Series<T,U> {
 impl<T,U> Index<usize> for Series<T,U> {
     type Output = DataPoint<T,U>;
     fn index(&self, index: usize) -> &DataPoint<T,U> {
-        &self.data[index]
+        &self.points()[index]
     }
 }
-
-
-impl<T: Clone,U: Clone> Iterator for Series<T,U> {
-    type Item = DataPoint<T,U>;
-    
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.counter < self.data.len() {
-            self.counter += 1;
-            Some( self.data[self.counter - 1].clone() )
-        } else {
-            None
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+use crate::{CorrelationMethod, QuantileMethod, RankMethod};
+
+/// Quantile of sorted values, q being in [0,1]
+pub fn quantile_sorted(sorted: &[f64], q: f64, method: &QuantileMethod) -> Option<f64> {
+    if sorted.is_empty() || !(0.0..=1.0).contains(&q) {
+        return None;
+    }
+    let h = q * (sorted.len() - 1) as f64;
+    let (lo, hi) = (h.floor() as usize, h.ceil() as usize);
+    let res = match method {
+        QuantileMethod::Linear => sorted[lo] + (h - lo as f64) * (sorted[hi] - sorted[lo]),
+        QuantileMethod::Lower => sorted[lo],
+        QuantileMethod::Higher => sorted[hi],
+        QuantileMethod::Midpoint => (sorted[lo] + sorted[hi]) / 2.0,
+        QuantileMethod::Nearest => sorted[h.round_ties_even() as usize]
+    };
+    Some( res )
+}
+
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some( values.iter().sum::<f64>() / values.len() as f64 )
+    }
+}
+
+/// Welford variance
+pub fn variance(values: &[f64], is_sample: bool) -> Option<f64> {
+    if values.len() <= 1 {
+        return None;
+    }
+    let mut m = 0.0f64;
+    let mut res = 0.0f64;
+    let mut n = 0.0f64;
+    for &x in values.iter() {
+        n += 1.0;
+        let dx = x - m;
+        m += dx / n;
+        res += dx * (x - m);
+    }
+    if is_sample {
+        Some( res / (n - 1.0) )
+    } else {
+        Some( res / n )
+    }
+}
+
+/// Central moments of order 2, 3 and 4
+fn central_moments(values: &[f64]) -> (f64, f64, f64) {
+    let n = values.len() as f64;
+    let m = values.iter().sum::<f64>() / n;
+    let (mut m2, mut m3, mut m4) = (0.0f64, 0.0f64, 0.0f64);
+    for &x in values.iter() {
+        let d = x - m;
+        m2 += d * d;
+        m3 += d * d * d;
+        m4 += d * d * d * d;
+    }
+    (m2 / n, m3 / n, m4 / n)
+}
+
+/// Adjusted Fisher-Pearson skewness
+pub fn skew(values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    if values.len() < 3 {
+        return None;
+    }
+    let (m2, m3, _) = central_moments(values);
+    if m2 == 0.0 {
+        return Some( 0.0 );
+    }
+    Some( (n * (n - 1.0)).sqrt() / (n - 2.0) * m3 / m2.powf(1.5) )
+}
+
+/// Unbiased excess kurtosis
+pub fn kurtosis(values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    if values.len() < 4 {
+        return None;
+    }
+    let (m2, _, m4) = central_moments(values);
+    if m2 == 0.0 {
+        return Some( 0.0 );
+    }
+    let g2 = m4 / (m2 * m2) - 3.0;
+    Some( ((n + 1.0) * g2 + 6.0) * (n - 1.0) / ((n - 2.0) * (n - 3.0)) )
+}
+
+pub fn covariance(xs: &[f64], ys: &[f64], is_sample: bool) -> Option<f64> {
+    if xs.len() <= 1 {
+        return None;
+    }
+    let mut m1 = 0.0f64;
+    let mut m2 = 0.0f64;
+    let mut res = 0.0f64;
+    let mut n = 0.0f64;
+    for (&x1, &x2) in xs.iter().zip(ys.iter()) {
+        n += 1.0;
+        let dx1 = x1 - m1;
+        m1 += dx1 / n;
+        m2 += (x2 - m2) / n;
+        res += dx1 * (x2 - m2);
+    }
+    if is_sample {
+        Some( res / (n - 1.0) )
+    } else {
+        Some( res / n )
+    }
+}
+
+pub fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let cov = covariance(xs, ys, false)?;
+    let v1 = variance(xs, false)?;
+    let v2 = variance(ys, false)?;
+    if v1 == 0.0 || v2 == 0.0 {
+        return None;
+    }
+    Some( cov / (v1.sqrt() * v2.sqrt()) )
+}
+
+/// Ranks starting at 1, ties being resolved following the method.
+/// RankMethod::First ranks tied values in their order of appearance.
+pub fn rank<T: PartialOrd>(values: &[T], method: &RankMethod, ascending: bool) -> Vec<f64> {
+    let mut order : Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a,&b| {
+        let ordering = values[a].partial_cmp(&values[b]).unwrap_or(Ordering::Equal);
+        if ascending { ordering } else { ordering.reverse() }
+    });
+    let mut ranks = vec![0.0f64; values.len()];
+    let mut dense = 0.0f64;
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        dense += 1.0;
+        for (k, &pos) in order[i..=j].iter().enumerate() {
+            ranks[pos] = match method {
+                RankMethod::Average => (i + j) as f64 / 2.0 + 1.0,
+                RankMethod::Min => i as f64 + 1.0,
+                RankMethod::Max => j as f64 + 1.0,
+                RankMethod::Dense => dense,
+                RankMethod::First => (i + k) as f64 + 1.0
+            };
+        }
+        i = j + 1;
+    }
+    ranks
+}
+
+pub fn spearman(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    pearson( &rank(xs, &RankMethod::Average, true), &rank(ys, &RankMethod::Average, true) )
+}
+
+/// Kendall tau-b, accounting for ties in either sample
+pub fn kendall(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len();
+    if n <= 1 {
+        return None;
+    }
+    let mut concordant = 0i64;
+    let mut discordant = 0i64;
+    let mut ties_x = 0i64;
+    let mut ties_y = 0i64;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let dx = xs[i] - xs[j];
+            let dy = ys[i] - ys[j];
+            if dx == 0.0 && dy == 0.0 {
+                continue;
+            } else if dx == 0.0 {
+                ties_x += 1;
+            } else if dy == 0.0 {
+                ties_y += 1;
+            } else if (dx > 0.0) == (dy > 0.0) {
+                concordant += 1;
+            } else {
+                discordant += 1;
+            }
+        }
+    }
+    let denominator = ((concordant + discordant + ties_x) as f64 * (concordant + discordant + ties_y) as f64).sqrt();
+    if denominator == 0.0 {
+        None
+    } else {
+        Some( (concordant - discordant) as f64 / denominator )
+    }
+}
+
+pub fn correlation(xs: &[f64], ys: &[f64], method: &CorrelationMethod) -> Option<f64> {
+    match method {
+        CorrelationMethod::Pearson => pearson(xs, ys),
+        CorrelationMethod::Spearman => spearman(xs, ys),
+        CorrelationMethod::Kendall => kendall(xs, ys)
+    }
+}
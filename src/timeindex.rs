@@ -0,0 +1,166 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Index which can be placed on a time line, in milliseconds since the Unix epoch
+pub trait TimeIndex: Ord + Clone {
+    fn to_millis(&self) -> i64;
+    /// Index at a timestamp, truncated to the resolution of the index, None
+    /// when the timestamp is out of the range of the index
+    fn from_millis(millis: i64) -> Option<Self>;
+}
+
+impl TimeIndex for DateTime<Utc> {
+    fn to_millis(&self) -> i64 {
+        self.timestamp_millis()
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        Utc.timestamp_millis_opt(millis).single()
+    }
+}
+
+impl TimeIndex for DateTime<Local> {
+    fn to_millis(&self) -> i64 {
+        self.timestamp_millis()
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        Local.timestamp_millis_opt(millis).single()
+    }
+}
+
+/// Indices created from timestamps are expressed in UTC
+impl TimeIndex for DateTime<FixedOffset> {
+    fn to_millis(&self) -> i64 {
+        self.timestamp_millis()
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        Utc.timestamp_millis_opt(millis).single().map(|dt| dt.fixed_offset())
+    }
+}
+
+impl TimeIndex for NaiveDateTime {
+    fn to_millis(&self) -> i64 {
+        self.and_utc().timestamp_millis()
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        Utc.timestamp_millis_opt(millis).single().map(|dt| dt.naive_utc())
+    }
+}
+
+impl TimeIndex for NaiveDate {
+    fn to_millis(&self) -> i64 {
+        self.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis()
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        Utc.timestamp_millis_opt(millis).single().map(|dt| dt.date_naive())
+    }
+}
+
+/// Raw timestamps in milliseconds
+impl TimeIndex for i64 {
+    fn to_millis(&self) -> i64 {
+        *self
+    }
+
+    fn from_millis(millis: i64) -> Option<Self> {
+        Some( millis )
+    }
+}
+
+/// Time interval. Months are calendar months in UTC, counted as an average
+/// of 30.436875 days where a fixed length is needed.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Interval {
+    Milliseconds(i64),
+    Seconds(i64),
+    Minutes(i64),
+    Hours(i64),
+    Days(i64),
+    Weeks(i64),
+    Months(i64)
+}
+
+const MILLIS_PER_DAY : i64 = 86_400_000;
+
+impl Interval {
+    pub fn as_millis(&self) -> i64 {
+        match self {
+            Interval::Milliseconds(n) => *n,
+            Interval::Seconds(n) => n * 1_000,
+            Interval::Minutes(n) => n * 60_000,
+            Interval::Hours(n) => n * 3_600_000,
+            Interval::Days(n) => n * MILLIS_PER_DAY,
+            Interval::Weeks(n) => n * 7 * MILLIS_PER_DAY,
+            Interval::Months(n) => n * 2_629_746_000
+        }
+    }
+
+    /// Length in milliseconds, rejecting empty, negative or overflowing intervals
+    pub(crate) fn checked_millis(&self) -> Result<i64, &'static str> {
+        let (n, unit) = match self {
+            Interval::Milliseconds(n) => (n, 1),
+            Interval::Seconds(n) => (n, 1_000),
+            Interval::Minutes(n) => (n, 60_000),
+            Interval::Hours(n) => (n, 3_600_000),
+            Interval::Days(n) => (n, MILLIS_PER_DAY),
+            Interval::Weeks(n) => (n, 7 * MILLIS_PER_DAY),
+            Interval::Months(n) => (n, 2_629_746_000)
+        };
+        match n.checked_mul(unit) {
+            Some( millis ) if millis > 0 => Ok( millis ),
+            Some( _ ) => Err("Interval must be positive"),
+            None => Err("Interval is too long")
+        }
+    }
+
+    /// Start of the bucket [start, start + interval) holding a timestamp. Buckets
+    /// are aligned on the Unix epoch, weeks starting on Mondays and months on
+    /// multiples of the interval counted from January 1970.
+    pub(crate) fn bucket_start(&self, millis: i64) -> i64 {
+        match self {
+            Interval::Months(n) => month_start( month_of(millis).div_euclid(*n) * n ),
+            Interval::Weeks(_) => {
+                // 1970-01-05 is a Monday
+                let origin = 4 * MILLIS_PER_DAY;
+                origin + (millis - origin).div_euclid(self.as_millis()) * self.as_millis()
+            },
+            _ => millis.div_euclid(self.as_millis()) * self.as_millis()
+        }
+    }
+
+    /// Start of the bucket following the one starting at bucket_start
+    pub(crate) fn next_bucket(&self, bucket_start: i64) -> i64 {
+        match self {
+            Interval::Months(n) => month_start( month_of(bucket_start) + n ),
+            _ => bucket_start + self.as_millis()
+        }
+    }
+}
+
+/// Month holding a timestamp, counted from January 1970 in UTC
+fn month_of(millis: i64) -> i64 {
+    // Proleptic Gregorian calendar on days since 0000-03-01, in 400 year eras
+    let days = millis.div_euclid(MILLIS_PER_DAY) + 719_468;
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let year = days.div_euclid(146_097) * 400 + year_of_era + if month_from_march >= 10 { 1 } else { 0 };
+    let month0 = (month_from_march + 2) % 12;
+    (year - 1970) * 12 + month0
+}
+
+/// Timestamp of the first day of a month counted from January 1970
+fn month_start(month: i64) -> i64 {
+    let month0 = month.rem_euclid(12);
+    // Years starting in March put leap days at their end
+    let year = 1970 + month.div_euclid(12) - if month0 < 2 { 1 } else { 0 };
+    let month_from_march = (month0 + 10) % 12;
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * month_from_march + 2) / 5;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    (year.div_euclid(400) * 146_097 + day_of_era - 719_468) * MILLIS_PER_DAY
+}
@@ -1,6 +1,7 @@
 extern crate datatoolkit;
 
 use datatoolkit::FlexData;
+use datatoolkit::{helper, math};
 use std::convert::TryFrom;
 
 #[test]
@@ -36,4 +37,61 @@ fn operators() {
     let mut u = FlexData::from( 1 );
     u += FlexData::from( 5 );
     assert_eq!( u, FlexData::from( 6 ) );
-}
\ No newline at end of file
+
+    // Overflows give NA
+    assert_eq!( &FlexData::Uint(u32::MAX) + &FlexData::Uint(1), FlexData::NA );
+    assert_eq!( &FlexData::Uint(u32::MAX) * &FlexData::Uint(2), FlexData::NA );
+    assert_eq!( &FlexData::Int(i32::MIN) - &FlexData::Int(1), FlexData::NA );
+    assert_eq!( &FlexData::Int(i32::MAX) * &FlexData::Int(2), FlexData::NA );
+    assert_eq!( &FlexData::Int(i32::MIN) / &FlexData::Int(-1), FlexData::NA );
+    assert_eq!( &FlexData::Int(i32::MIN) % &FlexData::Int(-1), FlexData::NA );
+    let mut i = FlexData::Int(i32::MAX);
+    i += FlexData::Int(1);
+    assert_eq!( i, FlexData::NA );
+    // A sum stays NA once it overflows
+    let total : FlexData = vec![FlexData::Uint(u32::MAX), FlexData::Uint(1), FlexData::Uint(5)].into_iter().sum();
+    assert_eq!( total, FlexData::NA );
+}
+
+#[test]
+fn math_functions() {
+    // Integers are promoted
+    assert_eq!( math::sqrt( &FlexData::Uint(9) ), FlexData::Dbl(3.0) );
+    assert_eq!( math::abs( &FlexData::Int(-4) ), FlexData::Dbl(4.0) );
+    assert_eq!( math::sign( &FlexData::Int(-4) ), FlexData::Dbl(-1.0) );
+    assert_eq!( math::pow( &FlexData::Int(2), 10.0 ), FlexData::Dbl(1024.0) );
+    assert_eq!( math::log10( &FlexData::Uint(1000) ), FlexData::Dbl(3.0) );
+    assert_eq!( math::log2( &FlexData::Dbl(0.25) ), FlexData::Dbl(-2.0) );
+
+    // Domain errors and other variants give NA
+    assert_eq!( math::sqrt( &FlexData::Dbl(-1.0) ), FlexData::NA );
+    assert_eq!( math::ln( &FlexData::Uint(0) ), FlexData::NA );
+    assert_eq!( math::inverse( &FlexData::Int(0) ), FlexData::NA );
+    assert_eq!( math::asin( &FlexData::Dbl(2.0) ), FlexData::NA );
+    assert_eq!( math::logit( &FlexData::Dbl(1.0) ), FlexData::NA );
+    assert_eq!( math::exp( &FlexData::Str("1".to_string()) ), FlexData::NA );
+    assert_eq!( math::cos( &FlexData::NA ), FlexData::NA );
+
+    assert_eq!( math::round( &FlexData::Dbl(2.346), 2 ), FlexData::Dbl(2.35) );
+    assert_eq!( math::round( &FlexData::Uint(1250), -2 ), FlexData::Dbl(1300.0) );
+    assert_eq!( math::floor( &FlexData::Dbl(-2.341), 1 ), FlexData::Dbl(-2.4) );
+    assert_eq!( math::ceil( &FlexData::Dbl(2.341), 1 ), FlexData::Dbl(2.4) );
+    // Values too large to be scaled are already rounded
+    assert_eq!( math::round( &FlexData::Dbl(1e300), 10 ), FlexData::Dbl(1e300) );
+    assert_eq!( math::floor( &FlexData::Dbl(-1e300), 10 ), FlexData::Dbl(-1e300) );
+    // Helper functions agree with math
+    assert_eq!( helper::inverse( &FlexData::Int(4) ), FlexData::Dbl(0.25) );
+    assert_eq!( helper::ln( &FlexData::Uint(1) ), math::ln( &FlexData::Uint(1) ) );
+    assert_eq!( helper::exp( &FlexData::Int(0) ), FlexData::Dbl(1.0) );
+    assert_eq!( helper::inverse( &FlexData::Uint(0) ), FlexData::NA );
+    assert_eq!( helper::ln( &FlexData::Int(-1) ), FlexData::NA );
+    assert_eq!( helper::exp( &FlexData::Dbl(1000.0) ), FlexData::NA );
+    assert_eq!( math::clip( &FlexData::Dbl(1.7), Some(0.0), Some(1.0) ), FlexData::Dbl(1.0) );
+    assert_eq!( math::clip( &FlexData::Int(-3), Some(0.0), None ), FlexData::Dbl(0.0) );
+
+    assert_eq!( math::sin( &FlexData::Int(0) ), FlexData::Dbl(0.0) );
+    assert_eq!( math::atan( &FlexData::Dbl(1.0) ), FlexData::Dbl(std::f64::consts::FRAC_PI_4) );
+    assert_eq!( math::sigmoid( &FlexData::Uint(0) ), FlexData::Dbl(0.5) );
+    let p = f64::try_from( &math::sigmoid( &math::logit( &FlexData::Dbl(0.2) ) ) ).unwrap();
+    assert!( (p - 0.2).abs() < 1e-12 );
+}
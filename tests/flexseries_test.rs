@@ -1,6 +1,11 @@
 extern crate datatoolkit;
+extern crate chrono;
 
-use datatoolkit::{FlexDataType, FlexSeries, FlexDataPoint, FlexData, FlexIndex};
+use std::convert::TryFrom;
+
+use datatoolkit::{FlexDataType, FlexSeries, FlexDataPoint, FlexData, FlexIndex, QuantileMethod, RankMethod, Decay, Interval, Alignment};
+use datatoolkit::helper::{index_intersection, index_union, index_difference, index_symmetric_difference};
+use chrono::{Duration, TimeZone, Utc};
 
 fn make_double_series1() -> FlexSeries {
     let datapoints = vec![
@@ -34,6 +39,28 @@ fn make_double_series2() -> FlexSeries {
     FlexSeries::from_vec("dummy2", FlexDataType::Dbl, datapoints)
 }
 
+fn make_series_with_gaps() -> FlexSeries {
+    let datapoints = vec![
+        FlexDataPoint::new(FlexIndex::Uint(0), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Dbl(1.0)),
+        FlexDataPoint::new(FlexIndex::Uint(2), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Uint(3), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Uint(4), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Uint(7), FlexData::Dbl(5.0)),
+        FlexDataPoint::new(FlexIndex::Uint(9), FlexData::Dbl(3.0)),
+        FlexDataPoint::new(FlexIndex::Uint(10), FlexData::NA)
+    ];
+    FlexSeries::from_vec("gaps", FlexDataType::Dbl, datapoints)
+}
+
+fn make_series(label: &str, values: Vec<f64>) -> FlexSeries {
+    let datapoints : Vec<FlexDataPoint> = values.into_iter()
+        .enumerate()
+        .map(|(i,x)| FlexDataPoint::new(FlexIndex::Uint(i), FlexData::Dbl(x)))
+        .collect();
+    FlexSeries::from_vec(label, FlexDataType::Dbl, datapoints)
+}
+
 #[test]
 fn getters() {
     let series1 = make_double_series1();
@@ -50,8 +77,8 @@ fn selectors() {
     let series1 = make_double_series1();
     assert_eq!( series1.at( &FlexIndex::Uint(3) ), Some( &FlexDataPoint::new(FlexIndex::Uint(3), FlexData::Dbl(3.6)) ) );
     assert_eq!( series1.at( &FlexIndex::Uint(12) ), None );
-    assert_eq!( series1.contains( &FlexIndex::Uint(3) ), true );
-    assert_eq!( series1.contains( &FlexIndex::Uint(12) ), false );
+    assert!( series1.contains( &FlexIndex::Uint(3) ) );
+    assert!( !series1.contains( &FlexIndex::Uint(12) ) );
     let subset = series1.get_subset( vec![FlexIndex::Uint(3), FlexIndex::Uint(12)] );
     assert_eq!( subset.get_size(), 1 );
 }
@@ -80,4 +107,372 @@ fn stats() {
     let corr = series1.pearson_correlation( &series2 ).unwrap();
     println!("{:?}", corr);
     assert!( (-0.8991f64 - corr).abs() < 1e-4 );
-}
\ No newline at end of file
+}
+
+#[test]
+fn na_imputation() {
+    let series = make_series_with_gaps();
+
+    let filled = series.fill_na( &FlexData::Dbl(0.0) );
+    assert!( !filled.has_na() );
+    assert_eq!( filled[2usize].get_data(), &FlexData::Dbl(0.0) );
+
+    let filled = series.ffill( Some(2) );
+    assert_eq!( filled[0usize].get_data(), &FlexData::NA );
+    assert_eq!( filled[3usize].get_data(), &FlexData::Dbl(1.0) );
+    assert_eq!( filled[4usize].get_data(), &FlexData::NA );
+    assert_eq!( filled[7usize].get_data(), &FlexData::Dbl(3.0) );
+
+    let filled = series.bfill( None );
+    assert_eq!( filled[0usize].get_data(), &FlexData::Dbl(1.0) );
+    assert_eq!( filled[2usize].get_data(), &FlexData::Dbl(5.0) );
+    assert_eq!( filled[7usize].get_data(), &FlexData::NA );
+
+    let filled = series.interpolate( false ).unwrap();
+    assert_eq!( filled[0usize].get_data(), &FlexData::NA );
+    assert_eq!( filled[2usize].get_data(), &FlexData::Dbl(2.0) );
+    assert_eq!( filled[4usize].get_data(), &FlexData::Dbl(4.0) );
+    let filled = series.interpolate( true ).unwrap();
+    assert_eq!( filled[4usize].get_data(), &FlexData::Dbl(3.0) );
+    let labelled = FlexSeries::from_vec("labelled", FlexDataType::Dbl, vec![
+        FlexDataPoint::new(FlexIndex::Str("a".to_string()), FlexData::Dbl(1.0)),
+        FlexDataPoint::new(FlexIndex::Str("b".to_string()), FlexData::NA)
+    ]);
+    assert!( labelled.interpolate( true ).is_err() );
+    assert!( labelled.interpolate( false ).is_ok() );
+    let words = FlexSeries::from_vec("words", FlexDataType::Str, vec![
+        FlexDataPoint::new(FlexIndex::Uint(0), FlexData::Str("a".to_string()))
+    ]);
+    assert!( words.interpolate( false ).is_err() );
+
+    assert_eq!( series.fill_na_mean()[0usize].get_data(), &FlexData::Dbl(3.0) );
+    assert_eq!( series.fill_na_median()[0usize].get_data(), &FlexData::Dbl(3.0) );
+}
+
+#[test]
+fn descriptive_stats() {
+    let series1 = make_double_series1();
+    assert_eq!( series1.count(), 10 );
+    assert!( (f64::try_from( &series1.sum() ).unwrap() - 17.2).abs() < 1e-9 );
+    assert_eq!( series1.min(), Some( FlexData::Dbl(0.1) ) );
+    assert_eq!( series1.max(), Some( FlexData::Dbl(3.6) ) );
+    assert!( (series1.median().unwrap() - 1.6).abs() < 1e-9 );
+    assert!( (series1.quantile(0.25, &QuantileMethod::Linear).unwrap() - 0.825).abs() < 1e-9 );
+    assert_eq!( series1.quantile(0.25, &QuantileMethod::Lower), Some(0.7) );
+    assert_eq!( series1.quantile(0.25, &QuantileMethod::Higher), Some(1.2) );
+    assert_eq!( series1.quantile(0.25, &QuantileMethod::Nearest), Some(0.7) );
+    assert!( (series1.quantile(0.25, &QuantileMethod::Midpoint).unwrap() - 0.95).abs() < 1e-9 );
+    assert!( (series1.std(true).unwrap() - 1.18115).abs() < 1e-5 );
+    assert!( (series1.skew().unwrap() - 0.11417).abs() < 1e-5 );
+    assert!( (series1.kurtosis().unwrap() + 1.21712).abs() < 1e-5 );
+
+    // NA values are skipped
+    let gaps = make_series_with_gaps();
+    assert_eq!( gaps.count(), 3 );
+    assert_eq!( gaps.sum(), FlexData::Dbl(9.0) );
+    assert_eq!( gaps.mean(), Some(3.0) );
+    assert_eq!( gaps.variance(true), Some(4.0) );
+
+    // Integer series
+    let goals = FlexSeries::from_vec("goals", FlexDataType::Uint, vec![
+        FlexDataPoint::new(FlexIndex::Uint(0), FlexData::Uint(1)),
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Uint(0)),
+        FlexDataPoint::new(FlexIndex::Uint(2), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Uint(3), FlexData::Uint(1)),
+        FlexDataPoint::new(FlexIndex::Uint(4), FlexData::Uint(3)),
+        FlexDataPoint::new(FlexIndex::Uint(5), FlexData::Uint(0))
+    ]);
+    assert_eq!( goals.sum(), FlexData::Uint(5) );
+    assert_eq!( goals.mean(), Some(1.0) );
+    assert_eq!( goals.median(), Some(1.0) );
+    assert_eq!( goals.max(), Some( FlexData::Uint(3) ) );
+    assert!( (goals.variance(true).unwrap() - 1.5).abs() < 1e-9 );
+    assert_eq!( goals.mode(), vec![FlexData::Uint(0), FlexData::Uint(1)] );
+    assert_eq!( goals.nunique(), 3 );
+    assert_eq!( goals.value_counts()[2], (FlexData::Uint(3), 1) );
+}
+
+#[test]
+fn ranking() {
+    let values = vec![FlexData::Dbl(2.5), FlexData::Dbl(1.5), FlexData::NA, FlexData::Dbl(2.5), FlexData::Dbl(4.0), FlexData::Dbl(1.5)];
+    let datapoints : Vec<FlexDataPoint> = values.into_iter()
+        .enumerate()
+        .map(|(i,x)| FlexDataPoint::new(FlexIndex::Uint(i), x))
+        .collect();
+    let odds = FlexSeries::from_vec("odds", FlexDataType::Dbl, datapoints);
+    let ranks = |method: RankMethod, ascending: bool, pct: bool| -> Vec<FlexData> {
+        odds.rank(&method, ascending, pct).get_data().into_iter().cloned().collect()
+    };
+    let dbl = |v: Vec<f64>| -> Vec<FlexData> {
+        let mut res : Vec<FlexData> = v.into_iter().map(FlexData::Dbl).collect();
+        res.insert(2, FlexData::NA);
+        res
+    };
+    assert_eq!( ranks(RankMethod::Average, true, false), dbl(vec![3.5, 1.5, 3.5, 5.0, 1.5]) );
+    assert_eq!( ranks(RankMethod::Min, true, false), dbl(vec![3.0, 1.0, 3.0, 5.0, 1.0]) );
+    assert_eq!( ranks(RankMethod::Max, true, false), dbl(vec![4.0, 2.0, 4.0, 5.0, 2.0]) );
+    assert_eq!( ranks(RankMethod::Dense, true, false), dbl(vec![2.0, 1.0, 2.0, 3.0, 1.0]) );
+    assert_eq!( ranks(RankMethod::First, true, false), dbl(vec![3.0, 1.0, 4.0, 5.0, 2.0]) );
+    assert_eq!( ranks(RankMethod::First, false, false), dbl(vec![2.0, 4.0, 3.0, 1.0, 5.0]) );
+    assert_eq!( ranks(RankMethod::Average, true, true), dbl(vec![3.5 / 5.0, 1.5 / 5.0, 3.5 / 5.0, 1.0, 1.5 / 5.0]) );
+    assert_eq!( ranks(RankMethod::Dense, true, true)[4], FlexData::Dbl(1.0) );
+
+    let x = make_series("x", vec![1.0, 2.0, 3.0, 4.0, 5.0, 0.5]);
+    let y = make_series("y", vec![5.0, 6.0, 7.0, 8.0, 7.0]);
+    assert!( (x.spearman_correlation(&y).unwrap() - 0.820782).abs() < 1e-6 );
+    assert!( (x.kendall_tau(&y).unwrap() - 0.737865).abs() < 1e-6 );
+    let flat = make_series("flat", vec![2.0, 2.0, 2.0, 2.0, 2.0]);
+    assert_eq!( x.pearson_correlation(&flat), None );
+}
+
+#[test]
+fn rolling() {
+    let series1 = make_double_series1();
+    let mean = series1.rolling(2, 2, false).unwrap().mean();
+    assert_eq!( mean[0usize].get_data(), &FlexData::NA );
+    assert!( (f64::try_from( mean[1usize].get_data() ).unwrap() - 1.85).abs() < 1e-9 );
+
+    let gaps = make_series_with_gaps();
+    let sum = gaps.rolling(3, 2, false).unwrap().sum();
+    assert_eq!( sum[1usize].get_data(), &FlexData::NA );
+    assert_eq!( sum[6usize].get_data(), &FlexData::Dbl(8.0) );
+    assert_eq!( sum[7usize].get_data(), &FlexData::Dbl(8.0) );
+    assert_eq!( gaps.rolling(3, 1, true).unwrap().max()[4usize].get_data(), &FlexData::Dbl(5.0) );
+    assert!( series1.rolling_span(Interval::Days(2), 1, false).is_err() );
+
+    let start = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+    let datapoints = vec![
+        FlexDataPoint::new(FlexIndex::from(start), FlexData::Dbl(1.0)),
+        FlexDataPoint::new(FlexIndex::from(start + Duration::days(1)), FlexData::Dbl(2.0)),
+        FlexDataPoint::new(FlexIndex::from(start + Duration::days(5)), FlexData::Dbl(4.0)),
+        FlexDataPoint::new(FlexIndex::from(start + Duration::days(6)), FlexData::NA)
+    ];
+    let daily = FlexSeries::from_vec("daily", FlexDataType::Dbl, datapoints);
+    let sum = daily.rolling_span(Interval::Days(2), 1, false).unwrap().sum();
+    assert_eq!( sum[1usize].get_data(), &FlexData::Dbl(3.0) );
+    assert_eq!( sum[2usize].get_data(), &FlexData::Dbl(4.0) );
+    assert_eq!( sum[3usize].get_data(), &FlexData::Dbl(4.0) );
+}
+
+#[test]
+fn signed_index() {
+    let datapoints = vec![
+        FlexDataPoint::new(FlexIndex::Int(-2), FlexData::Dbl(1.0)),
+        FlexDataPoint::new(FlexIndex::Int(-1), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Int(1), FlexData::Dbl(4.0))
+    ];
+    let series = FlexSeries::from_vec("signed", FlexDataType::Dbl, datapoints);
+    assert_eq!( series.at( &FlexIndex::from(-2i64) ).unwrap().get_data(), &FlexData::Dbl(1.0) );
+    assert_eq!( format!("{}", series[0usize].get_index()), "-2" );
+    assert_eq!( series.interpolate(true).unwrap()[1usize].get_data(), &FlexData::Dbl(2.0) );
+    assert_eq!( i64::try_from( series[2usize].get_index() ), Ok(1) );
+    assert!( FlexIndex::Int(-5) < FlexIndex::Int(3) );
+}
+
+#[test]
+fn rolling_empty_window() {
+    assert!( make_double_series1().rolling(0, 1, true).is_err() );
+}
+
+#[test]
+fn cumulative() {
+    let gaps = make_series_with_gaps();
+    let cumsum = gaps.cumsum();
+    assert_eq!( cumsum[0usize].get_data(), &FlexData::NA );
+    assert_eq!( cumsum[2usize].get_data(), &FlexData::NA );
+    assert_eq!( cumsum[5usize].get_data(), &FlexData::Dbl(6.0) );
+    assert_eq!( cumsum[6usize].get_data(), &FlexData::Dbl(9.0) );
+    assert_eq!( gaps.cumprod()[6usize].get_data(), &FlexData::Dbl(15.0) );
+    assert_eq!( gaps.cummax()[6usize].get_data(), &FlexData::Dbl(5.0) );
+    assert_eq!( gaps.cummin()[6usize].get_data(), &FlexData::Dbl(1.0) );
+    let cumcount = gaps.cumcount();
+    assert_eq!( cumcount.get_datatype(), &FlexDataType::Uint );
+    assert_eq!( cumcount[0usize].get_data(), &FlexData::Uint(0) );
+    assert_eq!( cumcount[7usize].get_data(), &FlexData::Uint(3) );
+
+    // Values after an overflow stay NA
+    let datapoints = vec![
+        FlexDataPoint::new(FlexIndex::Uint(0), FlexData::Uint(u32::MAX)),
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Uint(1)),
+        FlexDataPoint::new(FlexIndex::Uint(2), FlexData::NA),
+        FlexDataPoint::new(FlexIndex::Uint(3), FlexData::Uint(5))
+    ];
+    let cumsum = FlexSeries::from_vec("large", FlexDataType::Uint, datapoints).cumsum();
+    assert_eq!( cumsum[0usize].get_data(), &FlexData::Uint(u32::MAX) );
+    assert_eq!( cumsum[1usize].get_data(), &FlexData::NA );
+    assert_eq!( cumsum[3usize].get_data(), &FlexData::NA );
+}
+
+#[test]
+fn shifting() {
+    let series1 = make_double_series1();
+    let lagged = series1.shift(2);
+    assert_eq!( lagged[1usize].get_data(), &FlexData::NA );
+    assert_eq!( lagged[2usize].get_data(), &FlexData::Dbl(2.5) );
+    assert_eq!( series1.shift(-1)[-1i32].get_data(), &FlexData::NA );
+    assert_eq!( series1.shift(-1)[0usize].get_data(), &FlexData::Dbl(1.2) );
+
+    let diff = series1.diff(1);
+    assert_eq!( diff[0usize].get_data(), &FlexData::NA );
+    assert!( (f64::try_from( diff[1usize].get_data() ).unwrap() + 1.3).abs() < 1e-9 );
+    let pct = series1.pct_change(1);
+    assert!( (f64::try_from( pct[2usize].get_data() ).unwrap() - 2.0).abs() < 1e-9 );
+
+    let gaps = make_series_with_gaps();
+    assert_eq!( gaps.diff(1)[5usize].get_data(), &FlexData::NA );
+    assert_eq!( gaps.diff(1)[6usize].get_data(), &FlexData::Dbl(-2.0) );
+    let zero_base = make_series("zero", vec![0.0, 1.0]);
+    assert_eq!( zero_base.pct_change(1)[1usize].get_data(), &FlexData::NA );
+}
+
+#[test]
+fn ewm() {
+    let gaps = make_series_with_gaps();
+    let mean = gaps.ewm(Decay::Alpha(0.5), true, false, 1).unwrap().mean();
+    assert_eq!( mean[0usize].get_data(), &FlexData::NA );
+    assert_eq!( mean[2usize].get_data(), &FlexData::Dbl(1.0) );
+    assert!( (f64::try_from( mean[5usize].get_data() ).unwrap() - 5.0625 / 1.0625).abs() < 1e-9 );
+    let mean = gaps.ewm(Decay::Alpha(0.5), true, true, 1).unwrap().mean();
+    assert!( (f64::try_from( mean[5usize].get_data() ).unwrap() - 5.5 / 1.5).abs() < 1e-9 );
+    assert!( (f64::try_from( mean[7usize].get_data() ).unwrap() - 5.75 / 1.75).abs() < 1e-9 );
+    assert_eq!( gaps.ewm(Decay::Alpha(0.5), true, true, 3).unwrap().mean()[5usize].get_data(), &FlexData::NA );
+
+    let series1 = make_double_series1();
+    let series2 = make_double_series2();
+    let ewm = series1.ewm(Decay::Span(4.0), true, false, 1).unwrap();
+    let var = ewm.var(true);
+    let cov = ewm.cov(&series1, true);
+    assert_eq!( var[0usize].get_data(), &FlexData::Dbl(0.0) );
+    assert!( (f64::try_from( var[-1i32].get_data() ).unwrap() - f64::try_from( cov[-1i32].get_data() ).unwrap()).abs() < 1e-9 );
+    assert_eq!( ewm.cov(&series2, false).get_size(), series1.get_size() );
+}
+
+#[test]
+fn iterators() {
+    let mut series1 = make_double_series1();
+    assert_eq!( series1.iter().filter(|fdp| fdp.get_data() > &FlexData::Dbl(2.0)).count(), 4 );
+    for (index, value) in series1.iter_mut() {
+        if index == &FlexIndex::Uint(1) {
+            *value = FlexData::NA;
+        }
+    }
+    assert_eq!( series1[0usize].get_data(), &FlexData::NA );
+    let mut n = 0;
+    for fdp in &series1 {
+        n += fdp.has_na() as usize;
+    }
+    assert_eq!( n, 1 );
+    let owned : Vec<FlexDataPoint> = series1.clone().into_iter().collect();
+    assert_eq!( owned.len(), series1.get_size() );
+}
+
+#[test]
+fn index_ordering() {
+    let series1 = make_double_series1();
+    let shuffled = series1.get_subset( vec![FlexIndex::Uint(7), FlexIndex::Uint(2), FlexIndex::Uint(9)] );
+    let sorted = shuffled.sort_index(true);
+    assert_eq!( sorted.get_indices(), vec![&FlexIndex::Uint(2), &FlexIndex::Uint(7), &FlexIndex::Uint(9)] );
+    assert_eq!( shuffled.sort_index(false)[0usize].get_index(), &FlexIndex::Uint(9) );
+
+    // Binary operations follow the order of the left series
+    let sum = shuffled.add("sum", &FlexDataType::Dbl, &make_double_series2());
+    assert_eq!( sum.get_indices(), shuffled.get_indices() );
+
+    let reindexed = sorted.reindex( &[FlexIndex::Uint(9), FlexIndex::Uint(3), FlexIndex::Uint(2)], &FlexData::NA ).unwrap();
+    assert_eq!( reindexed.get_size(), 3 );
+    assert_eq!( reindexed[0usize].get_data(), &FlexData::Dbl(1.4) );
+    assert_eq!( reindexed[1usize].get_data(), &FlexData::NA );
+    assert_eq!( reindexed.at( &FlexIndex::Uint(2) ).unwrap().get_data(), &FlexData::Dbl(1.2) );
+    assert!( sorted.reindex( &[FlexIndex::Uint(3), FlexIndex::Uint(3)], &FlexData::NA ).is_err() );
+
+    // Positions stay valid after a removal
+    let mut removed = series1.clone();
+    removed.remove_at( &FlexIndex::Uint(2) );
+    assert_eq!( removed.get_size(), 9 );
+    assert_eq!( removed.at( &FlexIndex::Uint(5) ).unwrap().get_data(), &FlexData::Dbl(0.7) );
+    assert!( removed.at( &FlexIndex::Uint(2) ).is_none() );
+    removed.remove(0);
+    assert_eq!( removed.at( &FlexIndex::Uint(5) ).unwrap().get_data(), &FlexData::Dbl(0.7) );
+
+    let first = [FlexIndex::Uint(3), FlexIndex::Uint(1), FlexIndex::Uint(2)];
+    let other = [FlexIndex::Uint(4), FlexIndex::Uint(2), FlexIndex::Uint(3)];
+    let first : Vec<&FlexIndex> = first.iter().collect();
+    let other : Vec<&FlexIndex> = other.iter().collect();
+    assert_eq!( index_intersection(first.clone(), other.clone()), vec![FlexIndex::Uint(3), FlexIndex::Uint(2)] );
+    assert_eq!( index_union(first.clone(), other.clone()), vec![FlexIndex::Uint(3), FlexIndex::Uint(1), FlexIndex::Uint(2), FlexIndex::Uint(4)] );
+    assert_eq!( index_difference(first.clone(), other.clone()), vec![FlexIndex::Uint(1)] );
+    assert_eq!( index_symmetric_difference(first, other), vec![FlexIndex::Uint(1), FlexIndex::Uint(4)] );
+}
+
+#[test]
+fn aligned_operations() {
+    let a = make_double_series1().get_subset( vec![FlexIndex::Uint(1), FlexIndex::Uint(2), FlexIndex::Uint(3)] );
+    let b = make_double_series2().get_subset( vec![FlexIndex::Uint(2), FlexIndex::Uint(3), FlexIndex::Uint(4)] );
+    let close = |x: &FlexData, y: f64| (f64::try_from(x).unwrap() - y).abs() < 1e-9;
+
+    let diff = &a - &b;
+    assert_eq!( diff.get_indices(), vec![&FlexIndex::Uint(2), &FlexIndex::Uint(3)] );
+    assert!( close(diff[1usize].get_data(), 3.0) );
+
+    let diff = a.sub_aligned(&b, &Alignment::Outer, Some( FlexData::Dbl(0.0) )).unwrap();
+    assert_eq!( diff.get_size(), 4 );
+    assert!( close(diff[0usize].get_data(), 2.5) );
+    assert!( close(diff[3usize].get_data(), -3.1) );
+    let sum = a.add_aligned(&b, &Alignment::Left, None).unwrap();
+    assert_eq!( sum.get_size(), 3 );
+    assert_eq!( sum[0usize].get_data(), &FlexData::NA );
+    assert!( close((&a / &b)[0usize].get_data(), 1.2 / 2.2) );
+    assert!( close((&a * &FlexData::Dbl(2.0))[0usize].get_data(), 5.0) );
+
+    let lo = a.min_aligned(&b, &Alignment::Outer, None).unwrap();
+    let hi = a.max_aligned(&b, &Alignment::Outer, None).unwrap();
+    assert_eq!( lo[0usize].get_data(), &FlexData::NA );
+    assert_eq!( lo[1usize].get_data(), &FlexData::Dbl(1.2) );
+    assert_eq!( hi[1usize].get_data(), &FlexData::Dbl(2.2) );
+
+    let mask = a.gt_mask(&b, &Alignment::Inner).unwrap();
+    assert_eq!( mask.get_datatype(), &FlexDataType::Uint );
+    assert_eq!( mask.get_data(), vec![&FlexData::Uint(0), &FlexData::Uint(1)] );
+    assert_eq!( a.le_mask(&b, &Alignment::Outer).unwrap()[0usize].get_data(), &FlexData::NA );
+    let filtered = a.filter_mask(&mask);
+    assert_eq!( filtered.get_size(), 1 );
+    assert_eq!( filtered[0usize].get_index(), &FlexIndex::Uint(3) );
+
+    // Integer series are promoted when dividing and never underflow
+    let goals = FlexSeries::from_vec("goals", FlexDataType::Uint, vec![
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Uint(7)),
+        FlexDataPoint::new(FlexIndex::Uint(2), FlexData::Uint(3))
+    ]);
+    let conceded = FlexSeries::from_vec("conceded", FlexDataType::Uint, vec![
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Uint(2)),
+        FlexDataPoint::new(FlexIndex::Uint(2), FlexData::Uint(0))
+    ]);
+    let ratio = &goals / &conceded;
+    assert_eq!( ratio.get_data(), vec![&FlexData::Dbl(3.5), &FlexData::NA] );
+    assert_eq!( (&goals % &conceded).get_data(), vec![&FlexData::Uint(1), &FlexData::NA] );
+    assert_eq!( (&conceded - &goals).get_data(), vec![&FlexData::NA, &FlexData::NA] );
+    assert_eq!( goals.pow_aligned(&conceded, &Alignment::Inner, None).unwrap()[0usize].get_data(), &FlexData::Dbl(49.0) );
+    assert_eq!( (&goals / &FlexData::Uint(2))[0usize].get_data(), &FlexData::Dbl(3.5) );
+    let mixed = &goals + &a;
+    assert_eq!( mixed.get_datatype(), &FlexDataType::Dbl );
+    assert!( close(mixed[0usize].get_data(), 9.5) );
+
+    // Series of incompatible datatypes are rejected
+    let names = FlexSeries::from_vec("names", FlexDataType::Str, vec![
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Str("Salford".to_string()))
+    ]);
+    assert!( names.add_aligned(&goals, &Alignment::Inner, None).is_err() );
+    assert!( names.div_aligned(&goals, &Alignment::Inner, None).is_err() );
+    assert!( names.eq_mask(&goals, &Alignment::Inner).is_err() );
+    assert!( goals.max_aligned(&names, &Alignment::Inner, None).is_err() );
+    assert_eq!( (&goals + &FlexData::NA).get_data(), vec![&FlexData::NA, &FlexData::NA] );
+}
+
+#[test]
+#[should_panic(expected = "Series datatypes are not compatible")]
+fn incompatible_operands() {
+    let names = FlexSeries::from_vec("names", FlexDataType::Str, vec![
+        FlexDataPoint::new(FlexIndex::Uint(1), FlexData::Str("Salford".to_string()))
+    ]);
+    let _ = &names * &make_double_series1();
+}
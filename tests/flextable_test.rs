@@ -1,8 +1,15 @@
 extern crate datatoolkit;
 extern crate serde;
+extern crate rayon;
+extern crate chrono;
 
+use std::convert::TryFrom;
 use std::fs::read_to_string;
-use datatoolkit::{FlexTable, FlexData, FlexIndex, FlexDataType};
+use datatoolkit::{FlexTable, FlexData, FlexIndex, FlexDataType, FlexSeries, FlexDataPoint, FlexDataVector, CorrelationMethod, AsofDirection, Series, Interval, Side};
+use chrono::{NaiveDate, TimeZone, Utc};
+use datatoolkit::helper::{derive_datatype, make_data_from_index};
+use datatoolkit::math;
+use rayon::prelude::*;
 
 fn create_table() -> FlexTable {
     // Pandas Equivalent:
@@ -24,10 +31,20 @@ fn create_table() -> FlexTable {
     ];
 
     let text = read_to_string("./tests/E3.csv").expect("File Not Found");
-    FlexTable::from_csv(text.as_str(), headers.into_iter().map(String::from).collect(), datatypes)
+    FlexTable::from_csv(text.as_str(), headers.into_iter().map(String::from).collect(), datatypes).unwrap()
+}
+
+fn make_series<T: Into<FlexData>>(label: &str, values: Vec<T>) -> FlexSeries {
+    let datapoints : Vec<FlexDataPoint> = values.into_iter()
+        .enumerate()
+        .map(|(i,x)| FlexDataPoint::new(FlexIndex::Uint(i), x.into()))
+        .collect();
+    let datatype = derive_datatype( datapoints[0].get_data() );
+    FlexSeries::from_vec(label, datatype, datapoints)
 }
 
 #[test]
+#[allow(clippy::never_loop, clippy::bool_comparison)]
 fn csv_import() {
 
     let mut table = create_table();
@@ -48,7 +65,7 @@ fn csv_import() {
     // Pandas equivalent: df['GoalDiff'] = df['FTHG'] - df['FTAG']
     let series = table.extract_series(&["FTHG","FTAG"]);
     let gd_series = series[0].sub( "GoalDiff", &FlexDataType::Int, &series[1] );
-    table.add_series( gd_series );
+    table.add_series( gd_series ).unwrap();
     
     // Pandas equivalent: print( df.head(10) )
     table.print( Some(10) ); // print first 10 records only
@@ -74,4 +91,370 @@ fn csv_import() {
     assert!( corr < 0.0 );
 
     //table.to_csv("test.csv");
-}
\ No newline at end of file
+}
+
+#[test]
+fn multi_index() {
+    let mut table = create_table();
+    let num_records = table.num_records();
+    table.set_multi_index(&["Date","HomeTeam"]).unwrap();
+    assert_eq!( table.get_index_labels(), &vec![String::from("Date"), String::from("HomeTeam")] );
+    assert_eq!( table.num_series(), 8 );
+    assert_eq!( table.num_records(), num_records );
+
+    // All games of a given day
+    let key = FlexIndex::Tuple(vec![FlexIndex::Str("03/08/2019".to_string()), FlexIndex::Str("Salford".to_string())]);
+    assert!( table.contains( &key ) );
+    let day = table.at_partial( &[FlexIndex::Str("03/08/2019".to_string())] );
+    assert_eq!( day.num_records(), 12 );
+
+    // All home games of a given team
+    let salford = table.at_level( "HomeTeam", &FlexIndex::Str("Salford".to_string()) ).unwrap();
+    assert!( table.at_level( "AwayTeam", &FlexIndex::Str("Salford".to_string()) ).is_err() );
+    let groups = FlexTable::group_by_levels( &table, &["HomeTeam"] ).unwrap();
+    assert_eq!( groups.get( &FlexIndex::Str("Salford".to_string()) ).unwrap().num_records(), salford.num_records() );
+    assert_eq!( groups.values().map(|t| t.num_records()).sum::<usize>(), num_records );
+    assert!( FlexTable::group_by_levels( &table, &["HomeTeam", "Referee"] ).is_err() );
+
+    table.reset_index().unwrap();
+    assert!( table.get_index_labels().is_empty() );
+    assert_eq!( table.num_series(), 10 );
+    assert_eq!( &table.get_labels()[0..2], &[String::from("Date"), String::from("HomeTeam")] );
+    assert_eq!( table[0][1], FlexData::Str("Salford".to_string()) );
+    assert!( table.contains( &FlexIndex::Uint(0) ) );
+}
+
+#[test]
+fn index_round_trip() {
+    let mut table = create_table();
+    // Duplicate keys and unsupported datatypes are rejected
+    assert!( table.set_index("HomeTeam").is_err() );
+    assert!( table.set_index("B365H").is_err() );
+    assert!( table.set_index("Unknown").is_err() );
+    assert_eq!( table.num_series(), 10 );
+
+    table.renumber();
+    table.reset_index().unwrap();
+    assert_eq!( table.get_labels()[0], "index" );
+    assert_eq!( table.get_datatypes()[0], FlexDataType::Uint );
+    assert!( table.reset_index().is_err() );
+
+    table.set_index("index").unwrap();
+    assert_eq!( table.num_series(), 10 );
+    assert_eq!( table.at( &FlexIndex::Uint(24) ).unwrap(), table[24] );
+}
+
+#[test]
+fn char_index_round_trip() {
+    let mut table = FlexTable::new(vec![
+        make_series("name", vec!["x", "y"]),
+        make_series("grade", vec!['a', 'b'])
+    ]).unwrap();
+    // Characters would come back as strings, so they can not be indices
+    assert!( table.set_index("grade").is_err() );
+    assert_eq!( table.get_datatypes(), &vec![FlexDataType::Str, FlexDataType::Char] );
+    let original = table.clone();
+    table.set_index("name").unwrap();
+    table.reset_index().unwrap();
+    assert_eq!( table.get_labels(), original.get_labels() );
+    assert_eq!( table.get_datatypes(), original.get_datatypes() );
+    assert_eq!( table[1], original[1] );
+}
+
+#[test]
+fn slicing() {
+    let table = create_table();
+    let n = table.num_records();
+
+    let res = table.range(-3, -1);
+    assert_eq!( res.num_records(), 3 );
+    assert_eq!( res[0], table[n - 3] );
+    assert_eq!( table.range(10, 1000).num_records(), n - 10 );
+    assert_eq!( table.range(5, 2).num_records(), 0 );
+
+    let res = table.range_index( &FlexIndex::Uint(10), &FlexIndex::Uint(19) );
+    assert_eq!( res.num_records(), 10 );
+    assert_eq!( res[0], table[10] );
+
+    let res = table.loc( &[FlexIndex::Uint(24), FlexIndex::Uint(3)], &["AwayTeam","HomeTeam"] ).unwrap();
+    assert!( table.loc( &[FlexIndex::Uint(24)], &["Unknown"] ).is_err() );
+    assert_eq!( res.get_labels(), &vec![String::from("AwayTeam"), String::from("HomeTeam")] );
+    assert_eq!( res.num_records(), 2 );
+    assert_eq!( res[0][1], table[24][3] );
+
+    assert_eq!( table.head(5)[4], table[4] );
+    assert_eq!( table.tail(5)[0], table[n - 5] );
+    assert_eq!( table.tail(n + 5).num_records(), n );
+
+    let sample = table.sample(20, 42);
+    assert_eq!( sample.num_records(), 20 );
+    assert_eq!( sample.get_indices().len(), 20 );
+    assert_eq!( table.sample(20, 42)[7], sample[7] );
+}
+
+#[test]
+fn series_management() {
+    let mut table = create_table();
+
+    let series = table.extract_series(&["FTHG","FTAG"]);
+    let total = series[0].add( "FTHG", &FlexDataType::Uint, &series[1] );
+    assert!( table.add_series( total.clone() ).is_err() );
+    table.replace_series( total ).unwrap();
+    assert_eq!( table[0][5], FlexData::Uint(2) );
+    assert_eq!( table[1][5], FlexData::Uint(0) );
+
+    let subset = table.select(&["B365A","HomeTeam"]).unwrap();
+    assert_eq!( subset.get_labels(), &vec![String::from("B365A"), String::from("HomeTeam")] );
+    assert_eq!( subset.get_datatypes(), &vec![FlexDataType::Dbl, FlexDataType::Str] );
+    assert!( table.select(&["B365A","B365A"]).is_err() );
+
+    table.drop(&["Div","Time"]).unwrap();
+    assert_eq!( table.num_series(), 8 );
+    assert!( table.drop(&["Div"]).is_err() );
+
+    table.rename_all(&[("FTHG","Goals"), ("FTAG","AwayGoals")]).unwrap();
+    assert!( table.rename("Goals", "HomeTeam").is_err() );
+    table.rename("AwayGoals", "FTAG").unwrap();
+    assert_eq!( table.extract_series(&["Goals"])[0].get_label(), "Goals" );
+
+    assert!( table.reorder(&["HomeTeam","AwayTeam"]).is_err() );
+    table.reorder(&["HomeTeam","AwayTeam","Date","Goals","FTAG","B365H","B365D","B365A"]).unwrap();
+    assert_eq!( table[0][0], FlexData::Str("Salford".to_string()) );
+    assert_eq!( table[0][3], FlexData::Uint(2) );
+}
+
+#[test]
+fn na_imputation() {
+    let table = create_table();
+    assert!( table.has_na() );
+
+    let na_records = table.get_na();
+    let index = na_records.get_indices()[0].clone();
+
+    let filled = table.fill_na_series(&[("B365H", FlexData::Dbl(1.0)), ("B365D", FlexData::Dbl(1.0)), ("B365A", FlexData::Dbl(1.0))]).unwrap();
+    assert!( table.fill_na_series(&[("Unknown", FlexData::Dbl(1.0))]).is_err() );
+    assert!( !filled.has_na() );
+    assert_eq!( filled.at(&index).unwrap()[7], FlexData::Dbl(1.0) );
+
+    assert!( !table.ffill(None).has_na() );
+    assert!( !table.fill_na_median().has_na() );
+    assert!( !table.interpolate(true).unwrap().has_na() );
+    assert_eq!( table.interpolate(true).unwrap().get_datatypes()[5], FlexDataType::Dbl );
+}
+
+#[test]
+fn describe() {
+    let table = create_table();
+    let summary = table.describe();
+    summary.print( None );
+    assert_eq!( summary.get_labels(), table.get_labels() );
+    assert_eq!( summary.num_records(), 12 );
+    assert_eq!( summary.get_datatypes()[3], FlexDataType::Str );
+    assert_eq!( summary.get_datatypes()[7], FlexDataType::Dbl );
+
+    let count = summary.at( &FlexIndex::Str("count".to_string()) ).unwrap();
+    assert_eq!( count[7], FlexData::Dbl( (table.num_records() - 4) as f64 ) );
+    let na = summary.at( &FlexIndex::Str("na".to_string()) ).unwrap();
+    assert_eq!( na[7], FlexData::Dbl(4.0) );
+    assert_eq!( na[3], FlexData::Str("0".to_string()) );
+
+    let series = table.extract_series(&["B365H"]);
+    let median = summary.at( &FlexIndex::Str("50%".to_string()) ).unwrap();
+    assert_eq!( median[7], FlexData::Dbl( series[0].median().unwrap() ) );
+    let top = summary.at( &FlexIndex::Str("top".to_string()) ).unwrap();
+    assert_eq!( top[0], FlexData::Str("E3".to_string()) );
+    assert_eq!( top[7], FlexData::NA );
+}
+
+#[test]
+fn correlation_matrix() {
+    let small = FlexTable::new(vec![
+        make_series("x", vec![1.0, 2.0, 3.0, 4.0, 5.0]),
+        make_series("y", vec![5.0, 6.0, 7.0, 8.0, 7.0])
+    ]).unwrap();
+    let y = FlexIndex::Str("y".to_string());
+    let spearman = f64::try_from( &small.corr(&CorrelationMethod::Spearman).at(&y).unwrap()[0] ).unwrap();
+    assert!( (spearman - 0.820782).abs() < 1e-6 );
+    let kendall = f64::try_from( &small.corr(&CorrelationMethod::Kendall).at(&y).unwrap()[0] ).unwrap();
+    assert!( (kendall - 0.737865).abs() < 1e-6 );
+
+    let table = create_table();
+    let corr = table.corr(&CorrelationMethod::Pearson);
+    assert_eq!( corr.get_labels(), &vec!["FTHG","FTAG","B365H","B365D","B365A"].into_iter().map(String::from).collect::<Vec<String>>() );
+    let home = corr.at( &FlexIndex::Str("B365H".to_string()) ).unwrap();
+    let away = corr.at( &FlexIndex::Str("B365A".to_string()) ).unwrap();
+    assert!( (f64::try_from( &home[2] ).unwrap() - 1.0).abs() < 1e-9 );
+    assert_eq!( home[4], away[2] );
+
+    // Pairwise NA deletion matches the series correlation on complete records
+    let series = table.drop_na().extract_series(&["B365H", "B365A"]);
+    let expected = series[0].pearson_correlation(&series[1]).unwrap();
+    assert!( (f64::try_from( &home[4] ).unwrap() - expected).abs() < 1e-9 );
+
+    let cov = table.cov(true);
+    let var = f64::try_from( &cov.at( &FlexIndex::Str("FTHG".to_string()) ).unwrap()[0] ).unwrap();
+    let std = table.extract_series(&["FTHG"])[0].std(true).unwrap();
+    assert!( (var - std * std).abs() < 1e-9 );
+}
+
+#[test]
+fn duplicate_labels() {
+    assert!( FlexTable::new(vec![ make_series("x", vec![1.0, 2.0]), make_series("x", vec![3.0, 4.0]) ]).is_err() );
+    let labels = vec![String::from("x"), String::from("y"), String::from("x")];
+    let datatypes = vec![FlexDataType::Dbl; 3];
+    let records = vec![ FlexDataVector::new(FlexIndex::Uint(0), vec![FlexData::Dbl(1.0); 3]) ];
+    assert!( FlexTable::from_vecs(labels, datatypes.clone(), records.clone()).is_err() );
+    let labels = vec![String::from("x"), String::from("y"), String::from("z")];
+    assert_eq!( FlexTable::from_vecs(labels, datatypes, records).unwrap().num_series(), 3 );
+    let text = "x,x\n1,2\n";
+    assert!( FlexTable::from_csv(text, vec![String::from("x"), String::from("x")], vec![FlexDataType::Uint; 2]).is_err() );
+}
+
+#[test]
+fn merge_asof() {
+    let trades = FlexTable::new(vec![
+        make_series("time", vec![FlexData::Uint(2), FlexData::Uint(5), FlexData::Uint(9), FlexData::Uint(1)]),
+        make_series("size", vec![FlexData::Dbl(10.0), FlexData::Dbl(20.0), FlexData::Dbl(30.0), FlexData::Dbl(40.0)])
+    ]).unwrap();
+    let quotes = FlexTable::new(vec![
+        make_series("bid", vec![FlexData::Dbl(99.5), FlexData::Dbl(100.5), FlexData::Dbl(101.5)]),
+        make_series("time", vec![FlexData::Uint(4), FlexData::Uint(2), FlexData::Uint(6)])
+    ]).unwrap();
+
+    let merged = trades.merge_asof(&quotes, "time", &AsofDirection::Backward, None).unwrap();
+    assert_eq!( merged.get_labels(), &vec!["time","size","bid"].into_iter().map(String::from).collect::<Vec<String>>() );
+    assert_eq!( merged[0][2], FlexData::Dbl(100.5) );
+    assert_eq!( merged[1][2], FlexData::Dbl(99.5) );
+    assert_eq!( merged[2][2], FlexData::Dbl(101.5) );
+    assert_eq!( merged[3][2], FlexData::NA );
+
+    let merged = trades.merge_asof(&quotes, "time", &AsofDirection::Forward, None).unwrap();
+    assert_eq!( merged[1][2], FlexData::Dbl(101.5) );
+    assert_eq!( merged[2][2], FlexData::NA );
+    let merged = trades.merge_asof(&quotes, "time", &AsofDirection::Nearest, Some(2.0)).unwrap();
+    assert_eq!( merged[2][2], FlexData::NA );
+    assert_eq!( merged[3][2], FlexData::Dbl(100.5) );
+    // Ties go to the earlier key
+    assert_eq!( merged[1][2], FlexData::Dbl(99.5) );
+
+    assert!( trades.merge_asof(&trades, "time", &AsofDirection::Backward, None).is_err() );
+    assert!( trades.merge_asof(&quotes, "price", &AsofDirection::Backward, None).is_err() );
+}
+
+#[test]
+fn iterators() {
+    let mut table = create_table();
+    let home_goals : u32 = table.iter()
+        .map(|v| u32::try_from( &v[5] ).unwrap_or(0))
+        .sum();
+    let mut total = 0;
+    for v in &table {
+        total += u32::try_from( &v[5] ).unwrap_or(0);
+    }
+    assert_eq!( home_goals, total );
+    assert_eq!( table.par_iter().filter(|v| v.has_na()).count(), table.get_na().num_records() );
+    for (_, record) in table.iter_mut() {
+        record[5] = FlexData::Uint(0);
+    }
+    assert!( table.iter().all(|v| v[5] == FlexData::Uint(0)) );
+    let records : Vec<_> = table.into_iter().collect();
+    assert_eq!( records.len(), 440 );
+}
+
+#[test]
+fn time_index() {
+    let table = create_table();
+    let mut salford = table.filter_all(&["HomeTeam"], |x| x == &FlexData::Str("Salford".to_string()));
+    assert!( table.clone().set_time_index("Date", "%d/%m/%Y").is_err() );
+    assert!( salford.clone().set_time_index("HomeTeam", "%d/%m/%Y").is_err() );
+
+    let kickoff = salford.nary_apply("Kickoff", FlexDataType::Str, &["Date","Time"], |xs| {
+        match (xs[0], xs[1]) {
+            (FlexData::Str(date), FlexData::Str(time)) => FlexData::Str( format!("{} {}", date, time) ),
+            _ => FlexData::NA
+        }
+    });
+    let mut by_kickoff = salford.clone();
+    by_kickoff.add_series( kickoff ).unwrap();
+    by_kickoff.set_time_index("Kickoff", "%d/%m/%Y %H:%M").unwrap();
+    assert_eq!( by_kickoff.get_index_labels(), &vec![String::from("Kickoff")] );
+    assert_eq!( by_kickoff[0].get_index(), &FlexIndex::from( Utc.with_ymd_and_hms(2019, 8, 3, 12, 30, 0).unwrap() ) );
+
+    salford.set_time_index("Date", "%d/%m/%Y").unwrap();
+    let first = NaiveDate::from_ymd_opt(2019, 8, 3).unwrap();
+    assert_eq!( salford[0].get_index(), &FlexIndex::Date(first) );
+    assert_eq!( format!("{}", salford[0].get_index()), "2019-08-03" );
+    let august = salford.range_index( &FlexIndex::from( NaiveDate::from_ymd_opt(2019, 8, 1).unwrap() ), &FlexIndex::from( NaiveDate::from_ymd_opt(2019, 8, 31).unwrap() ) );
+    assert_eq!( august.num_records(), 3 );
+
+    // Dates and timestamps are reset to ISO 8601 strings which parse back
+    let mut reset = salford.clone();
+    reset.reset_index().unwrap();
+    assert_eq!( reset[0][0], FlexData::Str("2019-08-03".to_string()) );
+    reset.set_time_index("Date", "%Y-%m-%d").unwrap();
+    assert_eq!( reset.get_indices(), salford.get_indices() );
+    let mut reset = by_kickoff.clone();
+    reset.reset_index().unwrap();
+    reset.set_time_index("Kickoff", "%+").unwrap();
+    assert_eq!( reset.get_indices(), by_kickoff.get_indices() );
+    assert!( make_data_from_index( &FlexIndex::Int(i64::MAX) ).is_err() );
+    assert!( make_data_from_index( &FlexIndex::Uint(usize::MAX) ).is_err() );
+
+    // Typed series can be resampled on the calendar
+    let odds = salford.extract_series(&["B365H"]).remove(0);
+    let typed : Series<NaiveDate,f64> = odds.try_to_series().unwrap();
+    assert_eq!( typed[0i32].get_index(), &first );
+    let monthly = typed.resample(Interval::Months(1), Side::Left, Side::Left).unwrap().count();
+    assert_eq!( monthly[0i32].get(), &3 );
+    assert_eq!( monthly[1i32].get(), &3 );
+}
+
+#[test]
+fn index_ordering() {
+    let table = create_table();
+    let n = table.num_records();
+    let indices = table.get_indices();
+    assert_eq!( indices.len(), n );
+    assert_eq!( indices[0], FlexIndex::Uint(0) );
+    assert_eq!( indices[n - 1], FlexIndex::Uint(n - 1) );
+
+    let reversed = table.sort_index(false);
+    assert_eq!( reversed[0], table[n - 1] );
+    assert_eq!( reversed.sort_index(true).get_indices(), indices );
+
+    let reindexed = table.reindex( &[FlexIndex::Uint(5), FlexIndex::Uint(100000), FlexIndex::Uint(2)], &FlexData::NA ).unwrap();
+    assert_eq!( reindexed.num_records(), 3 );
+    assert_eq!( reindexed[0], table[5] );
+    assert!( reindexed[1].get_data().iter().all(|x| x == &FlexData::NA) );
+    assert_eq!( reindexed.get_indices()[2], FlexIndex::Uint(2) );
+    assert!( table.reindex( &[FlexIndex::Uint(5), FlexIndex::Uint(5)], &FlexData::NA ).is_err() );
+
+    let mut removed = table.clone();
+    removed.remove_record_at( &FlexIndex::Uint(3) );
+    assert_eq!( removed.at( &FlexIndex::Uint(10) ), Some( table[10].clone() ) );
+    assert_eq!( removed.at( &FlexIndex::Uint(3) ), None );
+    removed.remove_record(0);
+    assert_eq!( removed.at( &FlexIndex::Uint(10) ), Some( table[10].clone() ) );
+}
+
+#[test]
+fn math_functions() {
+    let table = create_table();
+    assert!( table.apply_as(&["Unknown"], &FlexDataType::Dbl, math::inverse).is_err() );
+
+    // Implied probabilities of the bookmaker odds and their overround
+    let probas = table.apply_as(&["B365H","B365D","B365A"], &FlexDataType::Dbl, math::inverse).unwrap();
+    assert_eq!( probas.get_labels(), table.get_labels() );
+    let margin = probas.nary_apply("Margin", FlexDataType::Dbl, &["B365H","B365D","B365A"], |xs| {
+        math::round( &(&(xs[0] + xs[1]) + xs[2]), 2 )
+    });
+    assert!( margin.get_data().iter().all(|x| x == &&FlexData::NA || f64::try_from(*x).unwrap() > 1.0) );
+
+    // Integer series are promoted
+    let goals = table.apply_as(&["FTHG"], &FlexDataType::Dbl, |x| math::pow(x, 2.0)).unwrap();
+    assert_eq!( goals.get_datatypes()[5], FlexDataType::Dbl );
+    let fthg = table.extract_series(&["FTHG"]).remove(0);
+    let squared = fthg.apply_as(&FlexDataType::Dbl, |x| math::pow(x, 2.0));
+    assert_eq!( goals.extract_series(&["FTHG"]).remove(0).get_data(), squared.get_data() );
+    assert_eq!( squared.get_datatype(), &FlexDataType::Dbl );
+}
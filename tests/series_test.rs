@@ -43,4 +43,93 @@ fn iterator() {
     let res : Vec<DataPoint<usize,char>> = ts.into_iter().collect();
     assert_eq!(res.len(), 7);
     assert_eq!(res[2].get(), &'g');
-}
\ No newline at end of file
+}
+
+#[test]
+fn borrowing_iterators() {
+    let mut ts = build_series();
+    let word : String = ts.iter().map(|dp| *dp.get()).collect();
+    assert_eq!( word, "vegapit" );
+    for (index, value) in ts.iter_mut() {
+        if index % 2 == 0 {
+            *value = value.to_ascii_uppercase();
+        }
+    }
+    let mut word = String::new();
+    for dp in &ts {
+        word.push( *dp.get() );
+    }
+    assert_eq!( word, "VeGaPiT" );
+    // Bounded series only yield their live points
+    let mut ts = Series::new("Feed", Some(2));
+    ts.extend( build_series(), |_| {} );
+    assert_eq!( ts.into_iter().map(|dp| *dp.get()).collect::<String>(), "it" );
+}
+
+#[test]
+fn sorted_inserts() {
+    // Unordered items are sorted once, the last of duplicated indices is kept
+    let ts = Series::from_vec("Test", vec![
+        DataPoint::new(3, 'a'),
+        DataPoint::new(1, 'e'),
+        DataPoint::new(3, 'b'),
+        DataPoint::new(0, 'v')
+    ]);
+    assert_eq!( ts.get_size(), 3 );
+    assert_eq!( ts[2i32].get(), &'b' );
+
+    let mut ts = Series::new("Test", Some(3));
+    ts.insert_update( DataPoint::new(5, 'x') );
+    ts.insert_update( DataPoint::new(1, 'y') );
+    ts.insert_update( DataPoint::new(3, 'z') );
+    ts.insert_update( DataPoint::new(3, 'w') );
+    assert_eq!( ts.range_at(&1, 3, 0).iter().map(|dp| *dp.get()).collect::<String>(), "ywx" );
+    ts.insert_update( DataPoint::new(4, 'u') );
+    assert_eq!( ts[0i32].get(), &'w' );
+    assert_eq!( ts.at(&1, 0), None );
+    assert_eq!( ts.range_at(&4, 3, -1).len(), 3 );
+
+    // Appending in index order stays fast on large series
+    let mut ts = Series::new("Ticks", None);
+    for i in 0..200_000usize {
+        ts.insert_update( DataPoint::new(i, i % 7) );
+    }
+    assert_eq!( ts.at(&150_000, 2).unwrap().get(), &(150_002 % 7) );
+}
+
+#[test]
+fn bounded() {
+    let mut ts = Series::new("Feed", Some(3));
+    assert_eq!( ts.insert_update( DataPoint::new(1, 'a') ), None );
+    assert_eq!( ts.insert_update( DataPoint::new(2, 'b') ), None );
+    assert_eq!( ts.insert_update( DataPoint::new(4, 'c') ), None );
+    assert_eq!( ts.insert_update( DataPoint::new(5, 'd') ), Some( DataPoint::new(1, 'a') ) );
+    // Out of order inserts evict the oldest point, possibly the inserted one
+    assert_eq!( ts.insert_update( DataPoint::new(3, 'e') ), Some( DataPoint::new(2, 'b') ) );
+    assert_eq!( ts.insert_update( DataPoint::new(0, 'f') ), Some( DataPoint::new(0, 'f') ) );
+    assert_eq!( ts.get_size(), 3 );
+    assert_eq!( ts[0i32].get(), &'e' );
+    assert_eq!( ts.at(&4, -1).unwrap().get(), &'e' );
+
+    let mut evicted : Vec<DataPoint<usize,char>> = Vec::new();
+    ts.extend( (6..16).map(|i| DataPoint::new(i, 'x')), |dp| evicted.push(dp) );
+    assert_eq!( evicted.len(), 10 );
+    assert_eq!( evicted[0].get_index(), &3 );
+    assert_eq!( ts.get_size(), 3 );
+    assert_eq!( ts[0i32].get_index(), &13 );
+    assert_eq!( ts.range(0, -1).len(), 3 );
+    assert_eq!( ts.iter().count(), 3 );
+    assert_eq!( ts.since(&14).len(), 2 );
+    // Derived series are not bounded
+    assert_eq!( ts.get_max_size(), Some(3) );
+    assert_eq!( ts.map(|c| c.to_ascii_uppercase()).get_max_size(), None );
+    // Cumulative series keep the bound
+    assert_eq!( ts.cummax().get_max_size(), Some(3) );
+    assert_eq!( ts.cummin().get_max_size(), Some(3) );
+    assert_eq!( ts.cumcount().get_max_size(), Some(3) );
+    let mut numbers : Series<usize,i32> = Series::new("Numbers", Some(2));
+    numbers.extend( (1..5).map(|i| DataPoint::new(i, i as i32)), |_| () );
+    assert_eq!( numbers.cumprod().get_max_size(), Some(2) );
+    assert_eq!( numbers.cumsum().get_max_size(), Some(2) );
+    assert_eq!( numbers.cumprod()[-1i32].get(), &12 );
+}
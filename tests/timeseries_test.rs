@@ -1,8 +1,8 @@
 extern crate datatoolkit;
 extern crate chrono;
 
-use datatoolkit::{DataPoint,Series};
-use chrono::{DateTime, Utc, TimeZone};
+use datatoolkit::{DataPoint,Series,Interval,Decay,Side,Ohlc,AsofDirection,Alignment,FlexSeries,FlexTable,FlexData,FlexDataType,FlexIndex};
+use chrono::{DateTime, Utc, TimeZone, NaiveDate, Duration};
 
 fn build_series() -> Series<DateTime<Utc>, usize> {
     let dps = vec![ 
@@ -38,6 +38,54 @@ fn getters() {
     assert_eq!( ts[0i32].get(), &122 ); // First element
 }
 
+#[test]
+fn asof() {
+    let ts = build_series();
+    let t = |m: u32, s: u32| Utc.with_ymd_and_hms(2008, 1, 1, 0, m, s).unwrap();
+    assert_eq!( ts.at_or_before(&t(2, 30)).unwrap().get(), &118 );
+    assert_eq!( ts.at_or_before(&t(2, 0)).unwrap().get(), &118 );
+    assert_eq!( ts.at_or_after(&t(2, 30)).unwrap().get(), &114 );
+    assert_eq!( ts.at_or_after(&t(5, 1)), None );
+    assert_eq!( ts.nearest(&t(2, 40), None).unwrap().get(), &114 );
+    assert_eq!( ts.nearest(&t(2, 30), None).unwrap().get(), &118 );
+    assert_eq!( ts.nearest(&t(7, 0), Some(Interval::Minutes(1))), None );
+    assert_eq!( ts.nearest(&t(6, 0), Some(Interval::Minutes(1))).unwrap().get(), &116 );
+
+    let quotes = Series::from_vec("Quotes", vec![
+        DataPoint::new(t(0, 30), 1.5),
+        DataPoint::new(t(3, 10), 2.5)
+    ]);
+    let merged = ts.merge_asof(&quotes, &AsofDirection::Backward, None);
+    assert_eq!( merged.get_size(), 6 );
+    assert_eq!( merged[0i32].get(), &(122, None) );
+    assert_eq!( merged[1i32].get(), &(120, Some(1.5)) );
+    assert_eq!( merged[-1i32].get(), &(116, Some(2.5)) );
+    let merged = ts.merge_asof(&quotes, &AsofDirection::Forward, Some(Interval::Seconds(30)));
+    assert_eq!( merged[0i32].get(), &(122, Some(1.5)) );
+    assert_eq!( merged[3i32].get(), &(114, Some(2.5)) );
+    assert_eq!( merged[2i32].get(), &(118, None) );
+}
+
+#[test]
+fn index_ranges() {
+    let ts = build_series();
+    let t = |m: u32| Utc.with_ymd_and_hms(2008, 1, 1, 0, m, 0).unwrap();
+    // Last two minutes of data
+    let latest = ts.since( &(*ts[-1i32].get_index() - Duration::minutes(2)) );
+    assert_eq!( latest.map(|dp| *dp.get()).collect::<Vec<usize>>(), vec![114, 117, 116] );
+    assert_eq!( ts.until(&t(1)).len(), 2 );
+    assert_eq!( ts.between(&t(1), &t(3), true, false).len(), 2 );
+    assert_eq!( ts.between(&t(1), &t(3), false, true).next().unwrap().get(), &118 );
+    assert_eq!( ts.between(&t(3), &t(1), true, true).len(), 0 );
+    assert_eq!( ts.slice(t(2)..).len(), 4 );
+    assert_eq!( ts.slice(..t(2)).len(), 2 );
+    assert_eq!( ts.slice(t(10)..).len(), 0 );
+    // Positions out of bounds are clamped
+    assert_eq!( ts.range(-10, 1).len(), 2 );
+    assert_eq!( ts.range(4, 10).len(), 2 );
+    assert!( ts.range(8, 10).is_empty() );
+}
+
 #[test]
 fn iterator() {
     let ts = build_series();
@@ -53,10 +101,285 @@ fn cumsum() {
     assert_eq!( ts[-1i32].get(), &707);
 }
 
+#[test]
+fn cumulative() {
+    let ts = build_series();
+    assert_eq!( ts.cummax()[-1i32].get(), &122 );
+    assert_eq!( ts.cummin()[3i32].get(), &114 );
+    assert_eq!( ts.cummin()[-1i32].get(), &114 );
+    assert_eq!( ts.cumcount()[-1i32].get(), &6 );
+
+    let equity = Series::from_vec("Equity", vec![
+        DataPoint::new(0i64, 1.0f64),
+        DataPoint::new(1i64, 1.1),
+        DataPoint::new(2i64, 0.88),
+        DataPoint::new(3i64, 1.1)
+    ]);
+    assert!( (equity.cumprod()[-1i32].get() - 1.06480).abs() < 1e-9 );
+    let drawdown = equity.pct_change(1);
+    assert_eq!( drawdown.get_size(), 4 );
+    assert!( drawdown[0i32].get().is_nan() );
+    assert!( (drawdown[2i32].get() + 0.2).abs() < 1e-9 );
+    let diff = equity.diff(-2);
+    assert_eq!( diff.get_size(), 4 );
+    assert!( (diff[0i32].get() - 0.12).abs() < 1e-9 );
+    assert!( diff[-1i32].get().is_nan() );
+
+    // Falling unsigned values and zero bases do not panic
+    let volume = Series::from_vec("Volume", vec![
+        DataPoint::new(0i64, 0u32),
+        DataPoint::new(1i64, 5u32),
+        DataPoint::new(2i64, 3u32)
+    ]);
+    assert_eq!( volume.diff(1)[2i32].get(), &-2.0 );
+    assert!( volume.pct_change(1)[1i32].get().is_nan() );
+}
+
+#[test]
+fn shift() {
+    let ts = build_series();
+    let lagged = ts.shift(1, 0);
+    assert_eq!( lagged.get_size(), 6 );
+    assert_eq!( lagged[0i32].get(), &0 );
+    assert_eq!( lagged[1i32].get(), &122 );
+    let led = ts.shift(-2, 0);
+    assert_eq!( led[0i32].get(), &118 );
+    assert_eq!( led[-1i32].get(), &0 );
+}
+
 #[test]
 fn insert() {
     let mut ts = build_series();
     let date = Utc.with_ymd_and_hms(2008, 1, 1,0, 2, 0).unwrap();
-    ts.insert_add( DataPoint::new( date.clone() , 5) );
+    ts.insert_add( DataPoint::new( date, 5) );
     assert_eq!( ts.at( &date, 0 ).unwrap().get(), &123);  
-}
\ No newline at end of file
+}
+
+#[test]
+fn rolling() {
+    let ts = build_series();
+
+    let mean = ts.rolling(3, 3, false).unwrap().mean();
+    assert!( mean[0i32].get().is_nan() );
+    assert_eq!( mean[2i32].get(), &120.0 );
+    assert!( (mean[-1i32].get() - 347.0 / 3.0).abs() < 1e-9 );
+    assert_eq!( ts.rolling(3, 3, false).unwrap().sum()[-1i32].get(), &347.0 );
+
+    let max = ts.rolling(3, 1, true).unwrap().max();
+    assert_eq!( max[0i32].get(), &122.0 );
+    assert_eq!( max[-1i32].get(), &117.0 );
+    assert_eq!( ts.rolling(3, 1, false).unwrap().min()[3i32].get(), &114.0 );
+    assert_eq!( ts.rolling(3, 1, false).unwrap().median()[4i32].get(), &117.0 );
+    assert!( (ts.rolling(3, 3, false).unwrap().std(true)[2i32].get() - 2.0).abs() < 1e-9 );
+    assert_eq!( ts.rolling(3, 1, false).unwrap().apply(|w| w.iter().cloned().fold(f64::MIN, f64::max) - w.iter().cloned().fold(f64::MAX, f64::min))[-1i32].get(), &3.0 );
+    assert!( ts.rolling(0, 1, false).is_err() );
+
+    // Points strictly less than 2 minutes old
+    let mean = ts.rolling_span(Interval::Minutes(2), 1, false).unwrap().mean();
+    assert_eq!( mean[-1i32].get(), &116.5 );
+    assert_eq!( mean[0i32].get(), &122.0 );
+    assert!( ts.rolling_span(Interval::Minutes(0), 1, false).is_err() );
+    assert!( ts.rolling_span(Interval::Days(-1), 1, false).is_err() );
+}
+
+#[test]
+fn ewm() {
+    let ts = build_series();
+
+    let mean = ts.ewm(Decay::Alpha(0.5), false, false, 1).unwrap().mean();
+    assert_eq!( mean[0i32].get(), &122.0 );
+    assert_eq!( mean[-1i32].get(), &116.4375 );
+    let mean = ts.ewm(Decay::Span(3.0), true, false, 1).unwrap().mean();
+    assert!( (mean[1i32].get() - 362.0 / 3.0).abs() < 1e-9 );
+    assert!( (ts.ewm(Decay::HalfLife(1.0), true, false, 2).unwrap().var(false)[1i32].get() - 2.0).abs() < 1e-9 );
+    assert!( ts.ewm(Decay::Alpha(0.5), true, false, 1).unwrap().var(false)[0i32].get().is_nan() );
+    let ewm = ts.ewm(Decay::CenterOfMass(1.0), true, false, 1).unwrap();
+    assert!( (ewm.cov(&ts, false)[-1i32].get() - ewm.var(false)[-1i32].get()).abs() < 1e-9 );
+
+    // Points one minute apart decay as a half-life of one point
+    let timed = ts.ewm_time(Interval::Minutes(1), true, false, 1).unwrap().mean();
+    assert!( (timed[-1i32].get() - ts.ewm(Decay::HalfLife(1.0), true, false, 1).unwrap().mean()[-1i32].get()).abs() < 1e-9 );
+
+    // Irregular timestamps in milliseconds
+    let irregular = Series::from_vec("Irregular", vec![
+        DataPoint::new(0i64, 1.0),
+        DataPoint::new(1000i64, 2.0),
+        DataPoint::new(3000i64, 3.0)
+    ]);
+    let mean = irregular.ewm_time(Interval::Seconds(1), true, false, 1).unwrap().mean();
+    assert!( (mean[2i32].get() - 3.625 / 1.375).abs() < 1e-9 );
+    let mean = irregular.ewm_time(Interval::Seconds(1), false, false, 1).unwrap().mean();
+    assert_eq!( mean[2i32].get(), &2.625 );
+
+    // Decays which do not give a smoothing factor in (0,1] are rejected
+    assert!( ts.ewm(Decay::Alpha(0.0), true, false, 1).is_err() );
+    assert!( ts.ewm(Decay::Span(0.5), true, false, 1).is_err() );
+    assert!( ts.ewm(Decay::HalfLife(-1.0), true, false, 1).is_err() );
+    assert!( ts.ewm(Decay::CenterOfMass(-0.5), true, false, 1).is_err() );
+    assert!( irregular.ewm_time(Interval::Seconds(0), true, false, 1).is_err() );
+}
+
+#[test]
+fn resample() {
+    let ts = build_series();
+
+    let bars = ts.resample(Interval::Minutes(2), Side::Left, Side::Left).unwrap();
+    let sum = bars.sum();
+    assert_eq!( sum.get_size(), 3 );
+    assert_eq!( sum[0i32].get_index(), &Utc.with_ymd_and_hms(2008, 1, 1,0, 0, 0).unwrap() );
+    assert_eq!( sum[1i32].get(), &232 );
+    assert_eq!( bars.mean()[-1i32].get(), &116.5 );
+    assert_eq!( bars.count()[0i32].get(), &2 );
+    assert_eq!( bars.last()[1i32].get(), &114 );
+    assert_eq!( bars.ohlc()[0i32].get(), &Ohlc::new(122, 122, 120, 120) );
+
+    let bars = ts.resample(Interval::Minutes(2), Side::Right, Side::Right).unwrap().count();
+    assert_eq!( bars.get_size(), 4 );
+    assert_eq!( bars[0i32].get_index(), &Utc.with_ymd_and_hms(2008, 1, 1,0, 0, 0).unwrap() );
+    assert_eq!( bars[-1i32].get_index(), &Utc.with_ymd_and_hms(2008, 1, 1,0, 6, 0).unwrap() );
+    assert_eq!( bars[1i32].get(), &2 );
+
+    // Buckets may straddle the wrap of a bounded series
+    let mut ticks : Series<i64,i64> = Series::new("Ticks", Some(4));
+    ticks.extend( (0..11).map(|i| DataPoint::new(i * 1000, i)), |_| () );
+    let sum = ticks.resample(Interval::Seconds(2), Side::Left, Side::Left).unwrap().sum();
+    assert_eq!( sum.iter().map(|dp| *dp.get()).collect::<Vec<i64>>(), vec![7, 17, 10] );
+    assert_eq!( ticks.since(&8000).map(|dp| *dp.get()).collect::<Vec<i64>>(), vec![8, 9, 10] );
+
+    let daily = Series::from_vec("Daily", vec![
+        DataPoint::new(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(), 1.5),
+        DataPoint::new(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(), 2.5),
+        DataPoint::new(NaiveDate::from_ymd_opt(2024, 3, 2).unwrap(), 3.5)
+    ]);
+    let monthly = daily.resample(Interval::Months(1), Side::Left, Side::Left).unwrap();
+    assert_eq!( monthly.last().get_size(), 2 );
+    let upsampled = monthly.ffill();
+    assert_eq!( upsampled.get_size(), 3 );
+    assert_eq!( upsampled[1i32].get_index(), &NaiveDate::from_ymd_opt(2024, 2, 1).unwrap() );
+    assert_eq!( upsampled[1i32].get(), &2.5 );
+    assert_eq!( daily.resample(Interval::Months(1), Side::Left, Side::Right).unwrap().first()[0i32].get_index(), &NaiveDate::from_ymd_opt(2024, 2, 1).unwrap() );
+    // Weeks start on Mondays
+    let weekly = daily.resample(Interval::Weeks(1), Side::Left, Side::Left).unwrap().first();
+    assert_eq!( weekly[0i32].get_index(), &NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() );
+    assert_eq!( weekly[1i32].get_index(), &NaiveDate::from_ymd_opt(2024, 1, 29).unwrap() );
+
+    assert!( ts.resample(Interval::Days(0), Side::Left, Side::Left).is_err() );
+    assert!( ts.resample(Interval::Months(i64::MAX), Side::Left, Side::Left).is_err() );
+    // The bucket after the last date can not be labelled
+    let last = Series::from_vec("Last", vec![DataPoint::new(NaiveDate::MAX, 1.0)]);
+    assert!( last.resample(Interval::Days(1), Side::Left, Side::Left).is_ok() );
+    assert!( last.resample(Interval::Days(1), Side::Left, Side::Right).is_err() );
+}
+
+#[test]
+fn arithmetic() {
+    let t = |m: u32| Utc.with_ymd_and_hms(2008, 1, 1, 0, m, 0).unwrap();
+    let first = Series::from_vec("First", vec![
+        DataPoint::new(t(0), 10.0),
+        DataPoint::new(t(1), 12.0),
+        DataPoint::new(t(2), 9.0)
+    ]);
+    let second = Series::from_vec("Second", vec![
+        DataPoint::new(t(1), 8.0),
+        DataPoint::new(t(2), 6.0),
+        DataPoint::new(t(3), 5.0)
+    ]);
+
+    let spread = &first - &second;
+    assert_eq!( spread.get_size(), 2 );
+    assert_eq!( spread[0i32].get_index(), &t(1) );
+    assert_eq!( spread[0i32].get(), &4.0 );
+    assert_eq!( (&first / &second)[1i32].get(), &1.5 );
+    assert_eq!( (&first * 2.0)[2i32].get(), &18.0 );
+    assert_eq!( (&first + 1.0)[0i32].get(), &11.0 );
+
+    let total = first.add_aligned(&second, &Alignment::Outer, Some(0.0));
+    assert_eq!( total.get_size(), 4 );
+    assert_eq!( total[0i32].get(), &10.0 );
+    assert_eq!( total[-1i32].get(), &5.0 );
+    assert_eq!( first.sub_aligned(&second, &Alignment::Outer, None).get_size(), 2 );
+    let left = first.mul_aligned(&second, &Alignment::Left, Some(1.0));
+    assert_eq!( left.get_size(), 3 );
+    assert_eq!( left[0i32].get(), &10.0 );
+
+    // Divisions by zero are skipped, integer ones included
+    let goals = Series::from_vec("Goals", vec![DataPoint::new(t(0), 3u32), DataPoint::new(t(1), 4u32)]);
+    let shots = Series::from_vec("Shots", vec![DataPoint::new(t(0), 0u32), DataPoint::new(t(1), 2u32)]);
+    let ratio = &goals / &shots;
+    assert_eq!( ratio.get_size(), 1 );
+    assert_eq!( ratio[0i32].get(), &2 );
+    assert_eq!( goals.div_aligned(&shots, &Alignment::Left, None).get_size(), 1 );
+    // A zero scalar gives an empty series, keeping the id
+    let empty = &goals / 0;
+    assert_eq!( empty.get_size(), 0 );
+    assert_eq!( empty.get_id(), "Goals" );
+    assert_eq!( (&first / 0.0).get_size(), 0 );
+    // Only the points overflowing are skipped for other scalars
+    let signed = Series::from_vec("Signed", vec![DataPoint::new(t(0), i32::MIN), DataPoint::new(t(1), 4)]);
+    let negated = &signed / -1;
+    assert_eq!( negated.get_size(), 1 );
+    assert_eq!( negated[0i32].get(), &-4 );
+
+    let labels = first.map(|x| if *x > 9.5 { "up" } else { "down" });
+    assert_eq!( labels[2i32].get(), &"down" );
+    let pairs = first.zip_with(&second, &Alignment::Outer, |x,y| Some( (x.copied(), y.copied()) ));
+    assert_eq!( pairs[0i32].get(), &(Some(10.0), None) );
+    assert_eq!( pairs[3i32].get(), &(None, Some(5.0)) );
+}
+
+#[test]
+fn flex_conversions() {
+    let ts = build_series().map(|x| *x as f64);
+    let mean = ts.rolling(2, 2, false).unwrap().mean();
+    let flex = FlexSeries::from(&mean);
+    assert_eq!( flex.get_label(), "Test" );
+    assert_eq!( flex.get_datatype(), &FlexDataType::Dbl );
+    assert_eq!( flex[0usize].get_data(), &FlexData::NA );
+    assert_eq!( flex[1usize].get_index(), &FlexIndex::from( Utc.with_ymd_and_hms(2008, 1, 1, 0, 1, 0).unwrap() ) );
+    assert_eq!( flex[1usize].get_data(), &FlexData::Dbl(121.0) );
+
+    // NA values must be dropped before extracting a typed series
+    assert!( flex.try_to_series::<DateTime<Utc>,f64>().is_err() );
+    let typed : Series<DateTime<Utc>,f64> = flex.drop_na().try_to_series().unwrap();
+    assert_eq!( typed.get_size(), 5 );
+    assert_eq!( typed[0i32].get_index(), mean[1i32].get_index() );
+    assert!( flex.drop_na().try_to_series::<usize,f64>().is_err() );
+
+    let volume = Series::from_vec("Volume", vec![
+        DataPoint::new(Utc.with_ymd_and_hms(2008, 1, 1, 0, 5, 0).unwrap(), 300u32),
+        DataPoint::new(Utc.with_ymd_and_hms(2008, 1, 1, 0, 6, 0).unwrap(), 200u32)
+    ]);
+    let table = FlexTable::align(vec![ FlexSeries::from(&ts), FlexSeries::from(&volume) ]).unwrap();
+    assert_eq!( table.num_records(), 7 );
+    assert_eq!( table[0usize][1], FlexData::NA );
+    assert_eq!( table[5usize][0], FlexData::Dbl(116.0) );
+    assert_eq!( table[5usize][1], FlexData::Uint(300) );
+    assert_eq!( table[6usize][0], FlexData::NA );
+    assert!( FlexTable::align(vec![ FlexSeries::from(&ts), FlexSeries::from(&ts) ]).is_err() );
+}
+
+#[test]
+fn io() {
+    let ts = build_series();
+    let filepath = std::env::temp_dir().join("datatoolkit_series.csv");
+    let filepath = filepath.to_str().unwrap();
+    ts.to_csv(filepath, "time", "price", |t| t.to_rfc3339()).unwrap();
+    let text = std::fs::read_to_string(filepath).unwrap();
+    assert!( text.starts_with("time,price\n2008-01-01T00:00:00+00:00,122\n") );
+    let parse = |token: &str| DateTime::parse_from_rfc3339(token).ok().map(|t| t.with_timezone(&Utc));
+    let reloaded : Series<DateTime<Utc>,usize> = Series::from_csv("Reloaded", text.as_str(), "time", "price", parse).unwrap();
+    assert_eq!( reloaded.get_size(), 6 );
+    assert_eq!( reloaded[3i32], ts[3i32] );
+    assert!( Series::<DateTime<Utc>,usize>::from_csv("Bad", text.as_str(), "time", "volume", parse).is_err() );
+    assert!( Series::<DateTime<Utc>,usize>::from_csv("Bad", "time,price\nnow,1\n", "time", "price", parse).is_err() );
+
+    let mut bounded = Series::new("Bounded", Some(3));
+    bounded.extend( ts.clone(), |_| {} );
+    let json = bounded.to_json().unwrap();
+    assert!( json.starts_with("{\"id\":\"Bounded\",\"opt_max_size\":3,") );
+    let reloaded : Series<DateTime<Utc>,usize> = Series::from_json(json.as_str()).unwrap();
+    assert_eq!( reloaded.get_id(), "Bounded" );
+    assert_eq!( reloaded.get_max_size(), Some(3) );
+    assert_eq!( reloaded.iter().map(|dp| *dp.get()).collect::<Vec<usize>>(), vec![114, 117, 116] );
+    assert!( Series::<DateTime<Utc>,usize>::from_json("{}").is_err() );
+}